@@ -0,0 +1,149 @@
+//! XRPL classic address encoding
+//!
+//! Classic addresses are the base58check encoding (XRPL alphabet, type prefix
+//! `0x00` on XRPL chains, see [`XrplNetwork::address_prefix`]) of a 20-byte
+//! account ID. Ledger keys such as NFTokenPage indexes embed the raw account
+//! ID, so converting between the two is needed to look up or interpret them.
+//!
+//! [`XrplNetwork::address_prefix`]: crate::XrplNetwork::address_prefix
+
+use crate::error::{XnsError, XnsResult};
+use sha2::{Digest, Sha256};
+
+const ALPHABET: &[u8; 58] = b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz";
+
+pub(crate) const ACCOUNT_ID_PREFIX: u8 = 0x00;
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let digest = Sha256::digest(Sha256::digest(payload));
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Encode a 20-byte account ID as a classic address
+#[cfg(test)]
+pub(crate) fn encode_account_id(account_id: &[u8; 20]) -> String {
+    encode_account_id_with_prefix(ACCOUNT_ID_PREFIX, account_id)
+}
+
+/// Encode a 20-byte account ID as an address with a network's type prefix
+pub(crate) fn encode_account_id_with_prefix(prefix: u8, account_id: &[u8; 20]) -> String {
+    let mut payload = Vec::with_capacity(25);
+    payload.push(prefix);
+    payload.extend_from_slice(account_id);
+    payload.extend_from_slice(&checksum(&payload));
+
+    // Repeated division of the big-endian payload by 58
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &payload {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let zeros = payload.iter().take_while(|&&b| b == 0).count();
+    std::iter::repeat_n(ALPHABET[0], zeros)
+        .chain(digits.iter().rev().map(|&d| ALPHABET[d as usize]))
+        .map(char::from)
+        .collect()
+}
+
+/// Decode an address to its 20-byte account ID
+///
+/// Any type prefix is accepted, so addresses of custom networks decode too.
+pub(crate) fn decode_account_id(address: &str) -> XnsResult<[u8; 20]> {
+    let invalid = || XnsError::InvalidInput(format!("Invalid XRPL address: {}", address));
+
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in address.bytes() {
+        let mut carry = ALPHABET.iter().position(|&a| a == c).ok_or_else(invalid)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let zeros = address.bytes().take_while(|&c| c == ALPHABET[0]).count();
+    let payload: Vec<u8> = std::iter::repeat_n(0, zeros).chain(bytes.into_iter().rev()).collect();
+
+    if payload.len() != 25 {
+        return Err(invalid());
+    }
+    if checksum(&payload[..21]) != payload[21..] {
+        return Err(invalid());
+    }
+
+    let mut account_id = [0u8; 20];
+    account_id.copy_from_slice(&payload[1..21]);
+    Ok(account_id)
+}
+
+/// Issuer account ID embedded in an NFTokenID (bytes 4..24)
+pub(crate) fn nft_issuer_id(nft_id: &str) -> Option<[u8; 20]> {
+    let bytes = hex::decode(nft_id).ok().filter(|b| b.len() == 32)?;
+    bytes[4..24].try_into().ok()
+}
+
+/// Issuer address embedded in an NFTokenID, with a network's type prefix
+pub(crate) fn nft_issuer(nft_id: &str, prefix: u8) -> Option<String> {
+    nft_issuer_id(nft_id).map(|id| encode_account_id_with_prefix(prefix, &id))
+}
+
+/// Taxon embedded in an NFTokenID
+///
+/// The ID stores the taxon scrambled with the token sequence (bytes 28..32)
+/// so that tokens of one taxon do not cluster on one NFTokenPage; this
+/// reverses the scrambling.
+pub(crate) fn nft_taxon(nft_id: &str) -> Option<u32> {
+    let bytes = hex::decode(nft_id).ok().filter(|b| b.len() == 32)?;
+    let scrambled = u32::from_be_bytes(bytes[24..28].try_into().ok()?);
+    let sequence = u32::from_be_bytes(bytes[28..32].try_into().ok()?);
+    Some(scrambled ^ 384_160_001u32.wrapping_mul(sequence).wrapping_add(2459))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: &str = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh";
+    const GENESIS_ID: &str = "B5F762798A53D543A014CAF8B297CFF8F2F937E8";
+
+    #[test]
+    fn test_account_id_roundtrip() {
+        let id = decode_account_id(GENESIS).unwrap();
+        assert_eq!(hex::encode_upper(id), GENESIS_ID);
+        assert_eq!(encode_account_id(&id), GENESIS);
+
+        assert_eq!(encode_account_id(&[0; 20]), "rrrrrrrrrrrrrrrrrrrrrhoLvTp");
+        assert!(decode_account_id("rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTi").is_err());
+        assert!(decode_account_id("not an address").is_err());
+    }
+
+    #[test]
+    fn test_nft_issuer() {
+        let nft_id = format!("00080000{}0000099B00000000", GENESIS_ID);
+        assert_eq!(nft_issuer(&nft_id, ACCOUNT_ID_PREFIX).as_deref(), Some(GENESIS));
+        assert_eq!(nft_issuer("0008", ACCOUNT_ID_PREFIX), None);
+
+        // Another type prefix encodes the same account differently
+        let other = nft_issuer(&nft_id, 0x23).unwrap();
+        assert_ne!(other, GENESIS);
+        assert_eq!(decode_account_id(&other).unwrap(), decode_account_id(GENESIS).unwrap());
+
+        assert_eq!(nft_taxon(&nft_id), Some(0));
+        // Taxon 7, sequence 1: 7 ^ (384160001 + 2459) = 0x16E5DA9B
+        let nft_id = format!("00080000{}16E5DA9B00000001", GENESIS_ID);
+        assert_eq!(nft_taxon(&nft_id), Some(7));
+    }
+}
@@ -0,0 +1,434 @@
+//! Resolver caching
+//!
+//! The resolver stores resolved domains, names known not to exist, NFT
+//! metadata and NFT owners in a [`ResolverCache`] backend, each kind with its
+//! own TTL. The default backend is an in-process moka cache; [`FileCache`]
+//! persists entries to disk and [`NoopCache`] disables caching. Deployers can
+//! implement [`ResolverCache`] over a shared store (Redis, memcached, ...) so
+//! horizontally-scaled workers share one cache.
+//!
+//! Hits and misses are counted per kind and can be read with
+//! [`XnsResolver::cache_stats`](crate::XnsResolver::cache_stats).
+
+use crate::error::{XnsError, XnsResult};
+use crate::models::{DomainInfo, NftMetadata, OwnerStatus};
+use async_trait::async_trait;
+use moka::Expiry;
+use moka::future::Cache;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Value stored in a resolver cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum CachedValue {
+    /// A resolved domain
+    Domain(Box<DomainInfo>),
+
+    /// A domain no naming service knows about
+    NotFound,
+
+    /// NFT metadata for a URI
+    Metadata(NftMetadata),
+
+    /// Current owner of an NFT
+    Owner { owner: String, status: OwnerStatus },
+}
+
+/// Storage backend for resolver caches
+///
+/// Keys are namespaced by the resolver (`domain:`, `metadata:`, `owner:`,
+/// `holder:`), so
+/// a single backend holds every kind of entry. Implementations should treat
+/// backend failures as misses rather than surfacing them: a broken cache must
+/// not break resolution.
+#[async_trait]
+pub trait ResolverCache: Send + Sync {
+    /// Get a live entry
+    async fn get(&self, key: &str) -> Option<CachedValue>;
+
+    /// Insert or replace an entry that expires after `ttl`
+    async fn insert(&self, key: &str, value: CachedValue, ttl: Duration);
+
+    /// Remove an entry
+    async fn invalidate(&self, key: &str);
+
+    /// Remove every entry
+    async fn invalidate_all(&self);
+}
+
+pub(crate) fn domain_key(domain: &str) -> String {
+    format!("domain:{}", domain)
+}
+
+pub(crate) fn metadata_key(uri_hex: &str) -> String {
+    format!("metadata:{}", uri_hex)
+}
+
+pub(crate) fn owner_key(nft_id: &str) -> String {
+    format!("owner:{}", nft_id)
+}
+
+pub(crate) fn holder_key(nft_id: &str) -> String {
+    format!("holder:{}", nft_id)
+}
+
+/// In-process cache backed by moka, with a TTL per entry
+///
+/// NFT metadata is kept in its own pool so a batch touching many new
+/// documents cannot evict resolved domains and owners.
+#[derive(Clone)]
+pub struct MokaCache {
+    inner: Cache<String, (CachedValue, Duration)>,
+    metadata: Cache<String, (CachedValue, Duration)>,
+}
+
+struct EntryTtl;
+
+impl Expiry<String, (CachedValue, Duration)> for EntryTtl {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &(CachedValue, Duration),
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(value.1)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &String,
+        value: &(CachedValue, Duration),
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(value.1)
+    }
+}
+
+impl MokaCache {
+    /// Create a cache holding at most `max_capacity` entries of each pool
+    pub fn new(max_capacity: u64) -> Self {
+        Self {
+            inner: Self::pool(max_capacity),
+            metadata: Self::pool(max_capacity),
+        }
+    }
+
+    /// Bound the NFT metadata pool separately
+    pub fn with_metadata_capacity(mut self, capacity: u64) -> Self {
+        self.metadata = Self::pool(capacity);
+        self
+    }
+
+    fn pool(capacity: u64) -> Cache<String, (CachedValue, Duration)> {
+        Cache::builder().max_capacity(capacity).expire_after(EntryTtl).build()
+    }
+
+    fn pool_for(&self, key: &str) -> &Cache<String, (CachedValue, Duration)> {
+        if key.starts_with("metadata:") { &self.metadata } else { &self.inner }
+    }
+}
+
+#[async_trait]
+impl ResolverCache for MokaCache {
+    async fn get(&self, key: &str) -> Option<CachedValue> {
+        self.pool_for(key).get(key).await.map(|(value, _)| value)
+    }
+
+    async fn insert(&self, key: &str, value: CachedValue, ttl: Duration) {
+        self.pool_for(key).insert(key.to_string(), (value, ttl)).await;
+    }
+
+    async fn invalidate(&self, key: &str) {
+        self.pool_for(key).invalidate(key).await;
+    }
+
+    async fn invalidate_all(&self) {
+        self.inner.invalidate_all();
+        self.metadata.invalidate_all();
+    }
+}
+
+/// Persistent cache storing one JSON file per entry in a directory
+///
+/// Entries survive restarts and can be shared by processes on the same
+/// filesystem. Files are written atomically (write then rename); expired
+/// entries are removed when read.
+pub struct FileCache {
+    dir: PathBuf,
+    temp_counter: AtomicU64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    key: String,
+    /// Unix timestamp in milliseconds
+    expires_at: u64,
+    value: CachedValue,
+}
+
+impl FileCache {
+    /// Open (and create if needed) a cache directory
+    pub fn new(dir: impl Into<PathBuf>) -> XnsResult<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            XnsError::ConfigError(format!("Failed to create cache directory {}: {}", dir.display(), e))
+        })?;
+
+        Ok(Self {
+            dir,
+            temp_counter: AtomicU64::new(0),
+        })
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.json", hex::encode(Sha256::digest(key.as_bytes()))))
+    }
+
+    fn now_millis() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl ResolverCache for FileCache {
+    async fn get(&self, key: &str) -> Option<CachedValue> {
+        let path = self.path_for(key);
+        let contents = tokio::fs::read(&path).await.ok()?;
+
+        match serde_json::from_slice::<FileEntry>(&contents) {
+            Ok(entry) if entry.key == key && entry.expires_at > Self::now_millis() => {
+                Some(entry.value)
+            }
+            Ok(entry) if entry.key != key => None,
+            Ok(_) => {
+                let _ = tokio::fs::remove_file(&path).await;
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Discarding corrupt cache file {}: {}", path.display(), e);
+                let _ = tokio::fs::remove_file(&path).await;
+                None
+            }
+        }
+    }
+
+    async fn insert(&self, key: &str, value: CachedValue, ttl: Duration) {
+        let entry = FileEntry {
+            key: key.to_string(),
+            expires_at: Self::now_millis().saturating_add(ttl.as_millis() as u64),
+            value,
+        };
+
+        let contents = match serde_json::to_vec(&entry) {
+            Ok(contents) => contents,
+            Err(e) => {
+                tracing::warn!("Failed to serialize cache entry {}: {}", key, e);
+                return;
+            }
+        };
+
+        let path = self.path_for(key);
+        let temp = path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            self.temp_counter.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        if let Err(e) = tokio::fs::write(&temp, contents).await {
+            tracing::warn!("Failed to write cache file {}: {}", temp.display(), e);
+            return;
+        }
+        if let Err(e) = tokio::fs::rename(&temp, &path).await {
+            tracing::warn!("Failed to move cache file into place {}: {}", path.display(), e);
+            let _ = tokio::fs::remove_file(&temp).await;
+        }
+    }
+
+    async fn invalidate(&self, key: &str) {
+        let _ = tokio::fs::remove_file(self.path_for(key)).await;
+    }
+
+    async fn invalidate_all(&self) {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Failed to list cache directory {}: {}", self.dir.display(), e);
+                return;
+            }
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+        }
+    }
+}
+
+/// Cache that stores nothing, disabling caching entirely
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopCache;
+
+#[async_trait]
+impl ResolverCache for NoopCache {
+    async fn get(&self, _key: &str) -> Option<CachedValue> {
+        None
+    }
+
+    async fn insert(&self, _key: &str, _value: CachedValue, _ttl: Duration) {}
+
+    async fn invalidate(&self, _key: &str) {}
+
+    async fn invalidate_all(&self) {}
+}
+
+/// Snapshot of cache hit/miss counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Resolutions answered from the domain cache
+    pub hits: u64,
+
+    /// Resolutions answered from the not-found cache
+    pub negative_hits: u64,
+
+    /// Resolutions answered with a stale domain while it was refreshed
+    pub stale_hits: u64,
+
+    /// Resolutions that had to query the ledger
+    pub misses: u64,
+
+    /// Metadata documents served from cache
+    pub metadata_hits: u64,
+
+    /// Metadata documents fetched from IPFS/HTTP
+    pub metadata_misses: u64,
+
+    /// NFT owners served from cache
+    pub owner_hits: u64,
+
+    /// NFT owners looked up on the ledger
+    pub owner_misses: u64,
+}
+
+impl CacheStats {
+    /// Fraction of resolutions answered from cache (fresh, stale or negative)
+    pub fn hit_ratio(&self) -> f64 {
+        let hits = self.hits + self.negative_hits + self.stale_hits;
+        let total = hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            hits as f64 / total as f64
+        }
+    }
+}
+
+/// Live cache counters shared by clones of a resolver
+#[derive(Debug, Default)]
+pub(crate) struct CacheCounters {
+    pub(crate) hits: AtomicU64,
+    pub(crate) negative_hits: AtomicU64,
+    pub(crate) stale_hits: AtomicU64,
+    pub(crate) misses: AtomicU64,
+    pub(crate) metadata_hits: AtomicU64,
+    pub(crate) metadata_misses: AtomicU64,
+    pub(crate) owner_hits: AtomicU64,
+    pub(crate) owner_misses: AtomicU64,
+}
+
+impl CacheCounters {
+    pub(crate) fn record(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            negative_hits: self.negative_hits.load(Ordering::Relaxed),
+            stale_hits: self.stale_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            metadata_hits: self.metadata_hits.load(Ordering::Relaxed),
+            metadata_misses: self.metadata_misses.load(Ordering::Relaxed),
+            owner_hits: self.owner_hits.load(Ordering::Relaxed),
+            owner_misses: self.owner_misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_moka_cache_per_entry_ttl() {
+        let cache = MokaCache::new(10);
+        let owner = |owner: &str| CachedValue::Owner { owner: owner.to_string(), status: OwnerStatus::Clio };
+        cache.insert("owner:a", owner("rAlice"), Duration::from_millis(50)).await;
+        cache.insert("owner:b", owner("rBob"), Duration::from_secs(60)).await;
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(cache.get("owner:a").await.is_none());
+        assert!(matches!(cache.get("owner:b").await, Some(CachedValue::Owner { owner, .. }) if owner == "rBob"));
+    }
+
+    #[tokio::test]
+    async fn test_file_cache_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("xns-file-cache-{}", std::process::id()));
+        let cache = FileCache::new(&dir).unwrap();
+
+        cache.insert("domain:ckelley.xrp", CachedValue::NotFound, Duration::from_secs(60)).await;
+        assert!(matches!(cache.get("domain:ckelley.xrp").await, Some(CachedValue::NotFound)));
+
+        cache.insert("domain:gone.xrp", CachedValue::NotFound, Duration::ZERO).await;
+        assert!(cache.get("domain:gone.xrp").await.is_none());
+
+        cache.invalidate_all().await;
+        assert!(cache.get("domain:ckelley.xrp").await.is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_moka_cache_bounds_metadata_separately() {
+        let cache = MokaCache::new(2).with_metadata_capacity(50);
+        let owner = |owner: &str| CachedValue::Owner { owner: owner.to_string(), status: OwnerStatus::Clio };
+        cache.insert("owner:a", owner("rAlice"), Duration::from_secs(60)).await;
+        cache.insert("owner:b", owner("rBob"), Duration::from_secs(60)).await;
+        for i in 0..20 {
+            let metadata: NftMetadata = serde_json::from_value(serde_json::json!({ "name": format!("name{}", i) })).unwrap();
+            cache.insert(&metadata_key(&i.to_string()), CachedValue::Metadata(metadata), Duration::from_secs(60)).await;
+        }
+
+        cache.inner.run_pending_tasks().await;
+        cache.metadata.run_pending_tasks().await;
+        assert_eq!(cache.inner.entry_count(), 2);
+        assert_eq!(cache.metadata.entry_count(), 20);
+        assert!(cache.get("owner:a").await.is_some());
+        assert!(matches!(cache.get("metadata:7").await, Some(CachedValue::Metadata(m)) if m.name == "name7"));
+    }
+
+    #[test]
+    fn test_hit_ratio() {
+        assert_eq!(CacheStats::default().hit_ratio(), 0.0);
+
+        let counters = CacheCounters::default();
+        CacheCounters::record(&counters.hits);
+        CacheCounters::record(&counters.negative_hits);
+        CacheCounters::record(&counters.misses);
+        CacheCounters::record(&counters.misses);
+
+        let stats = counters.snapshot();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.hit_ratio(), 0.5);
+    }
+}
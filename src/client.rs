@@ -1,40 +1,117 @@
 use crate::error::{XnsError, XnsResult};
 use crate::models::*;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde_json::json;
 
 pub use crate::models::XrplNetwork;
 
+/// Public Clio server used for Clio-only methods
+pub const DEFAULT_CLIO_URL: &str = "https://clio.xrpl.org";
+
+/// Default number of `account_tx` pages read per account history scan
+pub const DEFAULT_HISTORY_PAGES: usize = 5;
+
 /// XRPL RPC client
 #[derive(Clone)]
 pub struct XrplClient {
     pub(crate) client: Client,
     rpc_url: String,
+    fallback_rpc_urls: Vec<String>,
+    clio_url: String,
     network: XrplNetwork,
+    history_pages: usize,
 }
 
 impl XrplClient {
     /// Create a new XRPL client
     pub fn new(network: XrplNetwork) -> Self {
-        Self {
-            client: Client::new(),
-            rpc_url: network.rpc_url().to_string(),
-            network,
-        }
+        let rpc_url = network.rpc_url().to_string();
+        Self::with_url(network, rpc_url)
     }
 
     /// Create with custom RPC URL
     pub fn with_url(network: XrplNetwork, rpc_url: String) -> Self {
+        let clio_url = network.clio_url().to_string();
+        Self::with_endpoints(network, vec![rpc_url], clio_url, Client::new())
+    }
+
+    /// Create with explicit endpoints and HTTP client
+    ///
+    /// `rpc_urls` are tried in order until one succeeds; when empty the
+    /// network's default rippled server is used.
+    pub fn with_endpoints(
+        network: XrplNetwork,
+        rpc_urls: Vec<String>,
+        clio_url: String,
+        client: Client,
+    ) -> Self {
+        let mut rpc_urls = rpc_urls.into_iter();
+        let rpc_url = rpc_urls
+            .next()
+            .unwrap_or_else(|| network.rpc_url().to_string());
+
         Self {
-            client: Client::new(),
+            client,
             rpc_url,
+            fallback_rpc_urls: rpc_urls.collect(),
+            clio_url,
             network,
+            history_pages: DEFAULT_HISTORY_PAGES,
         }
     }
 
+    /// Set how many `account_tx` pages a history scan reads before giving up
+    pub fn with_history_pages(mut self, pages: usize) -> Self {
+        self.history_pages = pages.max(1);
+        self
+    }
+
+    /// Number of `account_tx` pages a history scan reads before giving up
+    pub fn history_pages(&self) -> usize {
+        self.history_pages
+    }
+
     /// Get network type
     pub fn network(&self) -> XrplNetwork {
-        self.network
+        self.network.clone()
+    }
+
+    /// Send a request to rippled, failing over to the next RPC URL on error
+    async fn rpc<T: DeserializeOwned>(&self, request: &RpcRequest) -> XnsResult<T> {
+        let mut last_error = None;
+
+        for url in std::iter::once(&self.rpc_url).chain(&self.fallback_rpc_urls) {
+            match self.post(url, request).await {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    tracing::warn!("XRPL RPC {} failed at {}: {}", request.method, url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| XnsError::RpcError("No RPC URL configured".to_string())))
+    }
+
+    async fn post<T: DeserializeOwned>(&self, url: &str, request: &RpcRequest) -> XnsResult<T> {
+        let response = self
+            .client
+            .post(url)
+            .json(request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(XnsError::RpcError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            )));
+        }
+
+        let rpc_response: RpcResponse<T> = response.json().await?;
+        Ok(rpc_response.result)
     }
 
     /// Query NFTs for an account
@@ -60,25 +137,13 @@ impl XrplClient {
 
             tracing::debug!("Querying XRPL: account_nfts for {}", account);
 
-            let response = self
-                .client
-                .post(&self.rpc_url)
-                .json(&request)
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                return Err(XnsError::RpcError(format!(
-                    "HTTP {}: {}",
-                    response.status(),
-                    response.text().await.unwrap_or_default()
-                )));
-            }
-
-            let rpc_response: RpcResponse<AccountNftsResult> = response.json().await?;
-            all_nfts.extend(rpc_response.result.nfts);
+            let result: AccountNftsResult = self.rpc(&request).await?;
+            all_nfts.extend(result.nfts.into_iter().map(|mut nft| {
+                nft.owner = Some(account.to_string());
+                nft
+            }));
 
-            marker = rpc_response.result.marker;
+            marker = result.marker;
             if marker.is_none() {
                 break;
             }
@@ -89,9 +154,6 @@ impl XrplClient {
 
     /// Get NFT info via Clio (includes current owner)
     pub async fn nft_info(&self, nft_id: &str) -> XnsResult<NftInfo> {
-        // Use Clio endpoint for nft_info
-        let clio_url = "https://clio.xrpl.org";
-
         let request = RpcRequest {
             method: "nft_info".to_string(),
             params: vec![json!({
@@ -103,7 +165,7 @@ impl XrplClient {
 
         let response = self
             .client
-            .post(clio_url)
+            .post(&self.clio_url)
             .json(&request)
             .send()
             .await?;
@@ -120,15 +182,23 @@ impl XrplClient {
         Ok(rpc_response.result)
     }
 
-    /// Query NFTs by issuer via Clio (more efficient for large collections)
-    pub async fn nfts_by_issuer(&self, issuer: &str, limit: Option<u32>) -> XnsResult<Vec<XrplNft>> {
-        let clio_url = "https://clio.xrpl.org";
-
+    /// Query NFTs by issuer via Clio (more efficient for large collections),
+    /// optionally only those minted under one taxon
+    pub async fn nfts_by_issuer(
+        &self,
+        issuer: &str,
+        taxon: Option<u32>,
+        limit: Option<u32>,
+    ) -> XnsResult<Vec<XrplNft>> {
         let mut params = json!({
             "issuer": issuer,
             "ledger_index": "validated"
         });
 
+        if let Some(taxon) = taxon {
+            params["nft_taxon"] = json!(taxon);
+        }
+
         if let Some(lim) = limit {
             params["limit"] = json!(lim);
         }
@@ -142,7 +212,7 @@ impl XrplClient {
 
         let response = self
             .client
-            .post(clio_url)
+            .post(&self.clio_url)
             .json(&request)
             .send()
             .await?;
@@ -168,6 +238,8 @@ impl XrplClient {
             uri: Option<String>,
             #[serde(default)]
             issuer: Option<String>,
+            #[serde(default)]
+            nft_taxon: Option<u32>,
         }
 
         let rpc_response: RpcResponse<NftsByIssuerResult> = response.json().await?;
@@ -178,12 +250,78 @@ impl XrplClient {
                 nft_token_id: nft.nft_id,
                 uri: nft.uri,
                 issuer: nft.issuer,
+                taxon: nft.nft_taxon,
+                owner: Some(nft.owner),
             }
         }).collect();
 
         Ok(nfts)
     }
 
+    /// Fetch an NFTokenPage by ledger index, or `None` if it does not exist
+    pub async fn nft_page(&self, index: &str) -> XnsResult<Option<NftPage>> {
+        tracing::debug!("Querying XRPL: ledger_entry nft_page {}", index);
+        self.ledger_entry(json!({ "nft_page": index })).await
+    }
+
+    /// Fetch an account's DID, or `None` if it has none
+    pub async fn did(&self, account: &str) -> XnsResult<Option<DidObject>> {
+        tracing::debug!("Querying XRPL: ledger_entry did {}", account);
+        self.ledger_entry(json!({ "did": account })).await
+    }
+
+    /// Fetch a validated ledger object selected by `params`, or `None` if it
+    /// does not exist
+    async fn ledger_entry<T: DeserializeOwned>(&self, mut params: serde_json::Value) -> XnsResult<Option<T>> {
+        params["ledger_index"] = json!("validated");
+        let request = RpcRequest {
+            method: "ledger_entry".to_string(),
+            params: vec![params],
+        };
+
+        let result: serde_json::Value = self.rpc(&request).await?;
+        match result.get("error").and_then(|e| e.as_str()) {
+            Some("entryNotFound") => Ok(None),
+            Some(error) => Err(XnsError::RpcError(format!("ledger_entry: {}", error))),
+            None => {
+                let node = result
+                    .get("node")
+                    .cloned()
+                    .ok_or_else(|| XnsError::ParseError("ledger_entry without node".to_string()))?;
+                Ok(Some(serde_json::from_value(node)?))
+            }
+        }
+    }
+
+    /// Query one page of an account's validated transactions, newest first
+    pub async fn account_tx(
+        &self,
+        account: &str,
+        marker: Option<serde_json::Value>,
+        limit: u32,
+    ) -> XnsResult<AccountTxResult> {
+        let mut params = json!({
+            "account": account,
+            "ledger_index_min": -1,
+            "ledger_index_max": -1,
+            "limit": limit,
+            "forward": false
+        });
+
+        if let Some(m) = marker {
+            params["marker"] = m;
+        }
+
+        let request = RpcRequest {
+            method: "account_tx".to_string(),
+            params: vec![params],
+        };
+
+        tracing::debug!("Querying XRPL: account_tx for {}", account);
+
+        self.rpc(&request).await
+    }
+
     /// Get account info
     pub async fn account_info(&self, account: &str) -> XnsResult<serde_json::Value> {
         let request = RpcRequest {
@@ -194,23 +332,7 @@ impl XrplClient {
             })],
         };
 
-        let response = self
-            .client
-            .post(&self.rpc_url)
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(XnsError::RpcError(format!(
-                "HTTP {}: {}",
-                response.status(),
-                response.text().await.unwrap_or_default()
-            )));
-        }
-
-        let rpc_response: RpcResponse<serde_json::Value> = response.json().await?;
-        Ok(rpc_response.result)
+        self.rpc(&request).await
     }
 }
 
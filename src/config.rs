@@ -0,0 +1,1117 @@
+//! Resolver configuration and builder
+//!
+//! [`XnsResolverBuilder`] collects every tunable of an [`XnsResolver`] into a
+//! [`ResolverConfig`], validates the combination and builds the resolver. The
+//! same config can be loaded from a TOML or JSON file.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use xns_sdk_rs::{NamingService, XnsResolver, XrplNetwork};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let resolver = XnsResolver::builder()
+//!         .network(XrplNetwork::Mainnet)
+//!         .rpc_url("https://xrplcluster.com")
+//!         .rpc_url("https://s2.ripple.com:51234")
+//!         .cache_ttl(Duration::from_secs(60))
+//!         .metadata_concurrency(20)
+//!         .services([NamingService::XNS])
+//!         .build()
+//!         .await?;
+//!
+//!     let info = resolver.resolve("ckelley.xrp").await?;
+//!     println!("Owner: {}", info.owner);
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ## Config file
+//!
+//! ```toml
+//! network = "mainnet"
+//! rpc_urls = ["https://xrplcluster.com", "https://s2.ripple.com:51234"]
+//! clio_url = "https://clio.xrpl.org"
+//! metadata_concurrency = 20
+//! request_timeout_secs = 15
+//! services = ["xns"]
+//! did_records = true
+//!
+//! [cache]
+//! backend = { kind = "file", path = "/var/cache/xns" }
+//! max_capacity = 5000
+//! ttl_secs = 600
+//! negative_ttl_secs = 30
+//! metadata_ttl_secs = 86400
+//!
+//! [custom_issuers]
+//! xns = ["rYhfynZDrde1uSvvQAYctApg6DnVE5HKm"]
+//!
+//! # Issuers for other networks, used when `network` is switched
+//! [issuers.testnet]
+//! xns = [{ address = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh", taxon = 7 }]
+//!
+//! [tlds]
+//! xns = ["xrp"]
+//!
+//! [expiry]
+//! mode = "enforce"
+//! grace_period_secs = 2592000
+//!
+//! [record_precedence]
+//! sources = ["did", "memo", "service", "metadata"]
+//! owner_first = true
+//! ```
+//!
+//! A standalone rippled or sidechain is configured as a custom network;
+//! its issuers are listed under its name:
+//!
+//! ```toml
+//! services = ["xns"]
+//!
+//! [network.custom]
+//! name = "local"
+//! rpc_url = "http://localhost:5005"
+//! ws_url = "ws://localhost:6006"
+//! network_id = 21337
+//!
+//! [issuers.local]
+//! xns = ["rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh"]
+//! ```
+
+use crate::cache::{FileCache, MokaCache, NoopCache, ResolverCache};
+use crate::client::{DEFAULT_CLIO_URL, DEFAULT_HISTORY_PAGES};
+use crate::domain::{DomainName, XRP_TLD, normalize_tld};
+use crate::error::{XnsError, XnsResult};
+use crate::issuers::{Issuer, IssuerRegistry, check_issuers};
+use crate::models::{CustomNetwork, NamingService, XrplNetwork};
+use crate::parser::DEFAULT_IPFS_GATEWAYS;
+use crate::provider::NameServiceProvider;
+use crate::records::RecordPrecedence;
+use crate::resolver::XnsResolver;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Upper bound on concurrent metadata requests per lookup
+pub const MAX_METADATA_CONCURRENCY: usize = 256;
+
+/// Pacing applied to metadata requests during an issuer scan
+///
+/// At most `every` requests are started per `delay` window, so a scan of
+/// many NFTs does not overwhelm the metadata host. A zero delay disables
+/// pacing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataPacing {
+    /// Number of requests started per window
+    pub every: usize,
+
+    /// Length of each window
+    #[serde(rename = "delay_ms", with = "duration_ms")]
+    pub delay: Duration,
+}
+
+impl Default for MetadataPacing {
+    fn default() -> Self {
+        Self {
+            every: 10,
+            delay: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Built-in cache backends selectable from configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum CacheBackend {
+    /// In-process moka cache
+    #[default]
+    Memory,
+
+    /// Persistent cache in a directory, see [`FileCache`]
+    File { path: PathBuf },
+
+    /// No caching
+    Disabled,
+}
+
+/// Cache settings
+///
+/// Metadata rarely changes once minted while ownership can change at any
+/// time, so each kind of data is cached with its own TTL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Storage backend, unless overridden with
+    /// [`XnsResolverBuilder::cache_backend`]
+    pub backend: CacheBackend,
+
+    /// Max number of cached domains (resolved and not-found)
+    pub max_capacity: u64,
+
+    /// How long a resolved domain stays cached
+    #[serde(rename = "ttl_secs", with = "duration_secs")]
+    pub ttl: Duration,
+
+    /// How long a not-found result stays cached
+    #[serde(rename = "negative_ttl_secs", with = "duration_secs")]
+    pub negative_ttl: Duration,
+
+    /// How long past `ttl` a resolved domain may still be served, marked
+    /// stale, while it is refreshed in the background. Zero disables
+    /// stale-while-revalidate.
+    #[serde(rename = "stale_window_secs", with = "duration_secs")]
+    pub stale_window: Duration,
+
+    /// Max number of cached NFT metadata documents
+    pub metadata_capacity: u64,
+
+    /// How long NFT metadata stays cached
+    #[serde(rename = "metadata_ttl_secs", with = "duration_secs")]
+    pub metadata_ttl: Duration,
+
+    /// How long an NFT's owner stays cached
+    #[serde(rename = "owner_ttl_secs", with = "duration_secs")]
+    pub owner_ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            backend: CacheBackend::Memory,
+            max_capacity: 1000,
+            ttl: Duration::from_secs(300),
+            negative_ttl: Duration::from_secs(60),
+            stale_window: Duration::ZERO,
+            metadata_capacity: 10_000,
+            metadata_ttl: Duration::from_secs(3600),
+            owner_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+/// What the resolver does with names that look like other names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfusableMode {
+    /// No confusable checks
+    Off,
+
+    /// Resolve, listing any findings in `DomainInfo::warnings`
+    #[default]
+    Flag,
+
+    /// Fail with `ConfusableDomain` instead of resolving
+    Refuse,
+}
+
+/// What `resolve` does when a name is registered with several naming
+/// services
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionPolicy {
+    /// Return the registration of the first service in `services` order
+    /// that knows the name, without asking the others
+    #[default]
+    Preferred,
+
+    /// Ask every service; fail with `AmbiguousDomain` when more than one
+    /// knows the name
+    RequireUnique,
+
+    /// Ask every service; return the preferred registration with the
+    /// others in `DomainInfo::other_registrations`
+    ReturnAll,
+}
+
+/// What the resolver does with names past their expiration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpiryMode {
+    /// Resolve every name as active
+    Ignore,
+
+    /// Resolve, reporting the state in `DomainInfo::expiry`
+    #[default]
+    Flag,
+
+    /// Fail with `DomainExpired` once the grace period has passed
+    Enforce,
+}
+
+/// Expiration handling settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExpiryConfig {
+    pub mode: ExpiryMode,
+
+    /// How long an expired name keeps resolving, in
+    /// `ExpiryStatus::GracePeriod`, before it is treated as expired
+    #[serde(rename = "grace_period_secs", with = "duration_secs")]
+    pub grace_period: Duration,
+}
+
+impl Default for ExpiryConfig {
+    fn default() -> Self {
+        Self {
+            mode: ExpiryMode::Flag,
+            grace_period: Duration::from_secs(30 * 24 * 3600),
+        }
+    }
+}
+
+/// Homograph and confusable detection settings
+///
+/// A name is reported when a label mixes scripts, or when its UTS-39
+/// skeleton matches a different name on the allowlist or, with
+/// `check_indexed`, a different name seen in naming service metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfusableConfig {
+    pub mode: ConfusableMode,
+
+    /// Names to protect from impersonation
+    pub allowlist: Vec<String>,
+
+    /// Also compare against names seen while scanning issuers
+    pub check_indexed: bool,
+
+    /// Max number of names seen while scanning issuers to remember; the
+    /// oldest are forgotten first
+    pub indexed_capacity: usize,
+}
+
+impl Default for ConfusableConfig {
+    fn default() -> Self {
+        Self {
+            mode: ConfusableMode::default(),
+            allowlist: Vec::new(),
+            check_indexed: false,
+            indexed_capacity: 100_000,
+        }
+    }
+}
+
+/// Complete resolver configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResolverConfig {
+    /// XRPL network to resolve against
+    pub network: XrplNetwork,
+
+    /// rippled JSON-RPC URLs, tried in order. Empty uses the network default.
+    pub rpc_urls: Vec<String>,
+
+    /// Clio server for `nft_info` and `nfts_by_issuer`. Left at the
+    /// default, a custom network uses its own Clio or RPC URL.
+    pub clio_url: String,
+
+    /// IPFS HTTP gateways, tried in order
+    pub ipfs_gateways: Vec<String>,
+
+    /// Domain cache settings
+    pub cache: CacheConfig,
+
+    /// Max concurrent metadata requests per lookup
+    pub metadata_concurrency: usize,
+
+    /// Pacing between groups of metadata requests
+    pub metadata_pacing: Option<MetadataPacing>,
+
+    /// Total timeout for each HTTP request
+    #[serde(rename = "request_timeout_secs", with = "duration_secs")]
+    pub request_timeout: Duration,
+
+    /// Timeout for establishing HTTP connections
+    #[serde(rename = "connect_timeout_secs", with = "duration_secs")]
+    pub connect_timeout: Duration,
+
+    /// User-Agent header sent with every HTTP request
+    pub user_agent: String,
+
+    /// Naming services queried, in order of preference
+    ///
+    /// Services other than XNS and XRP Domains need either custom issuers
+    /// or a provider registered with [`XnsResolverBuilder::provider`].
+    pub services: Vec<NamingService>,
+
+    /// Issuers on the configured network, replacing a service's other
+    /// issuers
+    pub custom_issuers: HashMap<NamingService, Vec<Issuer>>,
+
+    /// Issuers per network, replacing the built-in ones
+    pub issuers: IssuerRegistry,
+
+    /// TLDs served by a built-in provider, replacing the default `.xrp`
+    pub tlds: HashMap<NamingService, Vec<String>>,
+
+    /// Handling of names registered with several services
+    pub resolution: ResolutionPolicy,
+
+    /// Return domains with `OwnerStatus::Unknown` instead of failing with
+    /// `OwnerUnavailable` when no owner lookup succeeds
+    pub allow_unknown_owner: bool,
+
+    /// Homograph and confusable detection
+    pub confusables: ConfusableConfig,
+
+    /// Handling of expired names
+    pub expiry: ExpiryConfig,
+
+    /// Order in which record values from different sources are preferred
+    pub record_precedence: RecordPrecedence,
+
+    /// Add the addresses in the owner's `XNS_ADDRESSES` memo to every
+    /// resolved name (one `account_tx` scan per lookup)
+    pub memo_records: bool,
+
+    /// Add the records in the owner's XLS-40 DID to every resolved name
+    /// (one `ledger_entry` request per lookup), and look subdomains up there
+    /// before memos
+    pub did_records: bool,
+
+    /// Pages of 200 transactions read from an account's history when
+    /// looking for NFT transfers and owner memos. Busy accounts whose last
+    /// transfer is older are treated as having acquired the name before the
+    /// scanned history.
+    pub history_pages: usize,
+}
+
+impl ResolverConfig {
+    /// Default configuration for a network
+    pub fn new(network: XrplNetwork) -> Self {
+        Self {
+            network,
+            ..Self::default()
+        }
+    }
+
+    /// Load a config from a `.toml` or `.json` file
+    pub fn from_file(path: impl AsRef<Path>) -> XnsResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            XnsError::ConfigError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml_str(&contents),
+            Some("json") => Self::from_json_str(&contents),
+            _ => Err(XnsError::ConfigError(format!(
+                "Unsupported config file type (expected .toml or .json): {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Parse a TOML config
+    pub fn from_toml_str(contents: &str) -> XnsResult<Self> {
+        toml::from_str(contents)
+            .map_err(|e| XnsError::ConfigError(format!("Invalid TOML config: {}", e)))
+    }
+
+    /// Parse a JSON config
+    pub fn from_json_str(contents: &str) -> XnsResult<Self> {
+        serde_json::from_str(contents)
+            .map_err(|e| XnsError::ConfigError(format!("Invalid JSON config: {}", e)))
+    }
+
+    /// Issuers queried for a service on this network: custom issuers if
+    /// configured, then the issuer registry, then the built-in issuers
+    pub fn issuers_for(&self, service: &NamingService) -> Vec<Issuer> {
+        if let Some(issuers) = self.custom_issuers.get(service)
+            && !issuers.is_empty()
+        {
+            return issuers.clone();
+        }
+
+        match self.issuers.get(&self.network, service) {
+            [] => IssuerRegistry::builtin().get(&self.network, service).to_vec(),
+            issuers => issuers.to_vec(),
+        }
+    }
+
+    /// TLDs served by a service's built-in provider: configured TLDs if
+    /// any, otherwise `.xrp`
+    pub fn tlds_for(&self, service: &NamingService) -> Vec<String> {
+        match self.tlds.get(service) {
+            Some(tlds) if !tlds.is_empty() => {
+                tlds.iter().filter_map(|tld| normalize_tld(tld).ok()).collect()
+            }
+            _ => vec![XRP_TLD.to_string()],
+        }
+    }
+
+    /// Clio server actually queried
+    pub(crate) fn effective_clio_url(&self) -> &str {
+        if self.clio_url == DEFAULT_CLIO_URL {
+            self.network.clio_url()
+        } else {
+            &self.clio_url
+        }
+    }
+
+    /// Check that the configuration is complete and consistent
+    pub fn validate(&self) -> XnsResult<()> {
+        self.validate_with_providers(&[])
+    }
+
+    /// Validate, treating `provided` services as served by registered
+    /// providers rather than by issuers
+    pub(crate) fn validate_with_providers(&self, provided: &[NamingService]) -> XnsResult<()> {
+        for url in &self.rpc_urls {
+            check_http_url("RPC URL", url)?;
+        }
+        check_http_url("Clio URL", &self.clio_url)?;
+        if let XrplNetwork::Custom(network) = &self.network {
+            check_custom_network(network)?;
+        }
+
+        if self.ipfs_gateways.is_empty() {
+            return Err(XnsError::ConfigError("At least one IPFS gateway is required".to_string()));
+        }
+        for gateway in &self.ipfs_gateways {
+            check_http_url("IPFS gateway", gateway)?;
+        }
+
+        if self.cache.max_capacity == 0 {
+            return Err(XnsError::ConfigError("Cache capacity must be greater than 0".to_string()));
+        }
+        if self.cache.metadata_capacity == 0 {
+            return Err(XnsError::ConfigError("Metadata cache capacity must be greater than 0".to_string()));
+        }
+        if self.cache.ttl.is_zero()
+            || self.cache.negative_ttl.is_zero()
+            || self.cache.metadata_ttl.is_zero()
+            || self.cache.owner_ttl.is_zero()
+        {
+            return Err(XnsError::ConfigError("Cache TTLs must be greater than 0".to_string()));
+        }
+        if self.cache.negative_ttl > self.cache.ttl {
+            return Err(XnsError::ConfigError(
+                "Negative cache TTL cannot exceed the domain cache TTL".to_string(),
+            ));
+        }
+
+        if !(1..=MAX_METADATA_CONCURRENCY).contains(&self.metadata_concurrency) {
+            return Err(XnsError::ConfigError(format!(
+                "Metadata concurrency must be between 1 and {}, got {}",
+                MAX_METADATA_CONCURRENCY, self.metadata_concurrency
+            )));
+        }
+        if let Some(pacing) = &self.metadata_pacing
+            && pacing.every == 0
+        {
+            return Err(XnsError::ConfigError("Metadata pacing `every` must be greater than 0".to_string()));
+        }
+
+        if self.history_pages == 0 {
+            return Err(XnsError::ConfigError("History pages must be greater than 0".to_string()));
+        }
+
+        if self.request_timeout.is_zero() || self.connect_timeout.is_zero() {
+            return Err(XnsError::ConfigError("Timeouts must be greater than 0".to_string()));
+        }
+        if self.connect_timeout > self.request_timeout {
+            return Err(XnsError::ConfigError(
+                "Connect timeout cannot exceed the request timeout".to_string(),
+            ));
+        }
+
+        if self.user_agent.trim().is_empty() {
+            return Err(XnsError::ConfigError("User agent cannot be empty".to_string()));
+        }
+
+        if self.services.is_empty() {
+            return Err(XnsError::ConfigError("At least one naming service must be enabled".to_string()));
+        }
+        let mut seen = HashSet::new();
+        for service in &self.services {
+            if !seen.insert(service) {
+                return Err(XnsError::ConfigError(format!("Naming service {:?} listed twice", service)));
+            }
+        }
+
+        for (service, issuers) in &self.custom_issuers {
+            if !self.services.contains(service) {
+                return Err(XnsError::ConfigError(format!(
+                    "Custom issuers configured for disabled service {:?}",
+                    service
+                )));
+            }
+            check_issuers(service, issuers)?;
+        }
+        self.issuers.validate()?;
+
+        for (service, tlds) in &self.tlds {
+            if !self.services.contains(service) {
+                return Err(XnsError::ConfigError(format!(
+                    "TLDs configured for disabled service {:?}",
+                    service
+                )));
+            }
+            for tld in tlds {
+                normalize_tld(tld).map_err(|_| {
+                    XnsError::ConfigError(format!("Invalid TLD for {:?}: {}", service, tld))
+                })?;
+            }
+        }
+
+        if self.confusables.indexed_capacity == 0 {
+            return Err(XnsError::ConfigError("Indexed confusable capacity must be greater than 0".to_string()));
+        }
+        for name in &self.confusables.allowlist {
+            DomainName::parse(name).map_err(|e| {
+                XnsError::ConfigError(format!("Invalid confusable allowlist entry: {}", e))
+            })?;
+        }
+
+        for service in self.services.iter().filter(|s| !provided.contains(s)) {
+            if self.issuers_for(service).is_empty() {
+                return Err(XnsError::ConfigError(format!(
+                    "No issuer known for {:?} on {:?}; configure a custom issuer or registry entry",
+                    service, self.network
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ResolverConfig {
+    fn default() -> Self {
+        Self {
+            network: XrplNetwork::Mainnet,
+            rpc_urls: Vec::new(),
+            clio_url: DEFAULT_CLIO_URL.to_string(),
+            ipfs_gateways: DEFAULT_IPFS_GATEWAYS.iter().map(|g| g.to_string()).collect(),
+            cache: CacheConfig::default(),
+            metadata_concurrency: 10,
+            metadata_pacing: Some(MetadataPacing::default()),
+            request_timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            user_agent: concat!("xns-sdk-rs/", env!("CARGO_PKG_VERSION")).to_string(),
+            services: vec![NamingService::XNS, NamingService::XRPDomains],
+            custom_issuers: HashMap::new(),
+            issuers: IssuerRegistry::default(),
+            tlds: HashMap::new(),
+            resolution: ResolutionPolicy::default(),
+            allow_unknown_owner: false,
+            confusables: ConfusableConfig::default(),
+            expiry: ExpiryConfig::default(),
+            record_precedence: RecordPrecedence::default(),
+            memo_records: false,
+            did_records: false,
+            history_pages: DEFAULT_HISTORY_PAGES,
+        }
+    }
+}
+
+fn check_custom_network(network: &CustomNetwork) -> XnsResult<()> {
+    if network.name.trim().is_empty() {
+        return Err(XnsError::ConfigError("Custom network name must not be empty".to_string()));
+    }
+    if ["mainnet", "testnet", "devnet"].contains(&network.name.as_str()) {
+        return Err(XnsError::ConfigError(format!(
+            "Custom network name {} is reserved for the public network",
+            network.name
+        )));
+    }
+
+    check_http_url("Network RPC URL", &network.rpc_url)?;
+    if let Some(clio_url) = &network.clio_url {
+        check_http_url("Network Clio URL", clio_url)?;
+    }
+    if let Some(ws_url) = &network.ws_url
+        && !(ws_url.starts_with("ws://") || ws_url.starts_with("wss://"))
+    {
+        return Err(XnsError::ConfigError(format!(
+            "Network WebSocket URL must be a ws(s) URL: {}",
+            ws_url
+        )));
+    }
+    Ok(())
+}
+
+fn check_http_url(what: &str, url: &str) -> XnsResult<()> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(XnsError::ConfigError(format!("{} must be an http(s) URL: {}", what, url)))
+    }
+}
+
+impl CacheConfig {
+    /// Create the configured backend
+    ///
+    /// The in-memory backend bounds domains and owners by `max_capacity` and
+    /// NFT metadata by `metadata_capacity`.
+    pub fn build_backend(&self) -> XnsResult<Arc<dyn ResolverCache>> {
+        Ok(match &self.backend {
+            CacheBackend::Memory => Arc::new(
+                MokaCache::new(self.max_capacity).with_metadata_capacity(self.metadata_capacity),
+            ),
+            CacheBackend::File { path } => Arc::new(FileCache::new(path.clone())?),
+            CacheBackend::Disabled => Arc::new(NoopCache),
+        })
+    }
+}
+
+/// Builder for [`XnsResolver`]
+#[derive(Clone, Default)]
+pub struct XnsResolverBuilder {
+    config: ResolverConfig,
+    cache_backend: Option<Arc<dyn ResolverCache>>,
+    providers: Vec<Arc<dyn NameServiceProvider>>,
+}
+
+impl std::fmt::Debug for XnsResolverBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("XnsResolverBuilder")
+            .field("config", &self.config)
+            .field("custom_cache_backend", &self.cache_backend.is_some())
+            .field(
+                "providers",
+                &self.providers.iter().map(|p| p.service()).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl XnsResolverBuilder {
+    /// Start from the default mainnet configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from an existing configuration
+    pub fn from_config(config: ResolverConfig) -> Self {
+        Self {
+            config,
+            cache_backend: None,
+            providers: Vec::new(),
+        }
+    }
+
+    /// Start from a `.toml` or `.json` config file
+    pub fn from_file(path: impl AsRef<Path>) -> XnsResult<Self> {
+        ResolverConfig::from_file(path).map(Self::from_config)
+    }
+
+    /// Set the XRPL network
+    pub fn network(mut self, network: XrplNetwork) -> Self {
+        self.config.network = network;
+        self
+    }
+
+    /// Add a rippled RPC URL; URLs are tried in the order they were added
+    pub fn rpc_url(mut self, url: impl Into<String>) -> Self {
+        self.config.rpc_urls.push(url.into());
+        self
+    }
+
+    /// Replace the rippled RPC URLs
+    pub fn rpc_urls<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.rpc_urls = urls.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the Clio server URL
+    pub fn clio_url(mut self, url: impl Into<String>) -> Self {
+        self.config.clio_url = url.into();
+        self
+    }
+
+    /// Replace the IPFS gateways
+    pub fn ipfs_gateways<I, S>(mut self, gateways: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.ipfs_gateways = gateways.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Use a custom cache backend, e.g. a store shared between workers
+    pub fn cache_backend(mut self, backend: Arc<dyn ResolverCache>) -> Self {
+        self.cache_backend = Some(backend);
+        self
+    }
+
+    /// Set the max number of cached domains
+    pub fn cache_capacity(mut self, max_capacity: u64) -> Self {
+        self.config.cache.max_capacity = max_capacity;
+        self
+    }
+
+    /// Set how long resolved domains stay cached
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.config.cache.ttl = ttl;
+        self
+    }
+
+    /// Set how long not-found results stay cached
+    pub fn negative_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.config.cache.negative_ttl = ttl;
+        self
+    }
+
+    /// Serve expired domains for up to `window` past their TTL, marked
+    /// stale, while refreshing them in the background
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.config.cache.stale_window = window;
+        self
+    }
+
+    /// Set the max number of cached NFT metadata documents
+    pub fn metadata_cache_capacity(mut self, capacity: u64) -> Self {
+        self.config.cache.metadata_capacity = capacity;
+        self
+    }
+
+    /// Set how long NFT metadata stays cached
+    pub fn metadata_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.config.cache.metadata_ttl = ttl;
+        self
+    }
+
+    /// Set how long NFT owners stay cached
+    pub fn owner_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.config.cache.owner_ttl = ttl;
+        self
+    }
+
+    /// Set the max number of concurrent metadata requests per lookup
+    pub fn metadata_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.metadata_concurrency = concurrency;
+        self
+    }
+
+    /// Set metadata request pacing, or `None` to disable it
+    pub fn metadata_pacing(mut self, pacing: Option<MetadataPacing>) -> Self {
+        self.config.metadata_pacing = pacing;
+        self
+    }
+
+    /// Set the total timeout for each HTTP request
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = timeout;
+        self
+    }
+
+    /// Set the timeout for establishing HTTP connections
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = timeout;
+        self
+    }
+
+    /// Set the User-Agent header
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = user_agent.into();
+        self
+    }
+
+    /// Set which naming services are queried, in order of preference
+    pub fn services(mut self, services: impl IntoIterator<Item = NamingService>) -> Self {
+        self.config.services = services.into_iter().collect();
+        self
+    }
+
+    /// Add a custom issuer for a naming service
+    ///
+    /// Once a service has custom issuers, its built-in issuer is no longer
+    /// queried.
+    pub fn issuer(mut self, service: NamingService, issuer: impl Into<Issuer>) -> Self {
+        self.config
+            .custom_issuers
+            .entry(service)
+            .or_default()
+            .push(issuer.into());
+        self
+    }
+
+    /// Add issuers for any network, replacing the registry's entries for
+    /// the services it lists
+    pub fn issuer_registry(mut self, registry: IssuerRegistry) -> Self {
+        self.config.issuers.merge(registry);
+        self
+    }
+
+    /// Add a TLD served by a naming service's built-in provider
+    ///
+    /// Once a service has TLDs set, it no longer serves `.xrp` unless that
+    /// is added too.
+    pub fn tld(mut self, service: NamingService, tld: impl Into<String>) -> Self {
+        self.config.tlds.entry(service).or_default().push(tld.into());
+        self
+    }
+
+    /// Register a naming service provider
+    ///
+    /// The provider replaces the built-in one for its service. A service not
+    /// yet listed in [`services`](Self::services) is queried after the
+    /// listed ones.
+    pub fn provider(mut self, provider: Arc<dyn NameServiceProvider>) -> Self {
+        let service = provider.service();
+        self.providers.retain(|p| p.service() != service);
+        if !self.config.services.contains(&service) {
+            self.config.services.push(service);
+        }
+        self.providers.push(provider);
+        self
+    }
+
+    /// Set how names registered with several services are resolved
+    pub fn resolution_policy(mut self, policy: ResolutionPolicy) -> Self {
+        self.config.resolution = policy;
+        self
+    }
+
+    /// Resolve domains whose owner cannot be determined, marking them
+    /// `OwnerStatus::Unknown` instead of failing
+    pub fn allow_unknown_owner(mut self, allow: bool) -> Self {
+        self.config.allow_unknown_owner = allow;
+        self
+    }
+
+    /// Set how expired names are handled
+    pub fn expiry_mode(mut self, mode: ExpiryMode) -> Self {
+        self.config.expiry.mode = mode;
+        self
+    }
+
+    /// Set how long expired names keep resolving in their grace period
+    pub fn expiry_grace_period(mut self, grace_period: Duration) -> Self {
+        self.config.expiry.grace_period = grace_period;
+        self
+    }
+
+    /// Set the order in which record values from different sources are
+    /// preferred
+    pub fn record_precedence(mut self, precedence: RecordPrecedence) -> Self {
+        self.config.record_precedence = precedence;
+        self
+    }
+
+    /// Add the addresses the owner published in memos to resolved names
+    pub fn memo_records(mut self, enabled: bool) -> Self {
+        self.config.memo_records = enabled;
+        self
+    }
+
+    /// Add the records the owner stored in its DID to resolved names
+    pub fn did_records(mut self, enabled: bool) -> Self {
+        self.config.did_records = enabled;
+        self
+    }
+
+    /// Set how many pages of 200 transactions are read from an account's
+    /// history when tracing transfers and reading owner memos
+    pub fn history_pages(mut self, pages: usize) -> Self {
+        self.config.history_pages = pages;
+        self
+    }
+
+    /// Set how names confusable with other names are handled
+    pub fn confusable_mode(mut self, mode: ConfusableMode) -> Self {
+        self.config.confusables.mode = mode;
+        self
+    }
+
+    /// Protect names from impersonation by look-alike names
+    pub fn confusable_allowlist<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.confusables.allowlist.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Compare names against every name seen in naming service metadata
+    pub fn check_indexed_confusables(mut self, check: bool) -> Self {
+        self.config.confusables.check_indexed = check;
+        self
+    }
+
+    /// Set how many names seen in naming service metadata are remembered
+    /// for [`check_indexed_confusables`](Self::check_indexed_confusables)
+    pub fn indexed_confusables_capacity(mut self, capacity: usize) -> Self {
+        self.config.confusables.indexed_capacity = capacity;
+        self
+    }
+
+    /// The configuration built so far
+    pub fn config(&self) -> &ResolverConfig {
+        &self.config
+    }
+
+    /// Validate the configuration and build the resolver
+    pub async fn build(self) -> XnsResult<XnsResolver> {
+        let provided: Vec<NamingService> = self.providers.iter().map(|p| p.service()).collect();
+        self.config.validate_with_providers(&provided)?;
+        let backend = match self.cache_backend {
+            Some(backend) => backend,
+            None => self.config.cache.build_backend()?,
+        };
+        XnsResolver::from_config(self.config, backend, self.providers)
+    }
+}
+
+mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+mod duration_ms {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(ResolverConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_rejects_missing_issuer() {
+        let config = ResolverConfig::new(XrplNetwork::Testnet);
+        assert!(matches!(config.validate(), Err(XnsError::ConfigError(_))));
+
+        let config = XnsResolverBuilder::new()
+            .network(XrplNetwork::Testnet)
+            .services([NamingService::XNS])
+            .issuer(NamingService::XNS, "rYhfynZDrde1uSvvQAYctApg6DnVE5HKm")
+            .config()
+            .clone();
+        assert!(config.validate().is_ok());
+
+        let mut registry = IssuerRegistry::new();
+        registry.add(
+            &XrplNetwork::Testnet,
+            NamingService::XNS,
+            Issuer::new("rYhfynZDrde1uSvvQAYctApg6DnVE5HKm").with_taxon(7),
+        );
+        let config = XnsResolverBuilder::new()
+            .network(XrplNetwork::Testnet)
+            .services([NamingService::XNS])
+            .issuer_registry(registry)
+            .config()
+            .clone();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.issuers_for(&NamingService::XNS)[0].taxon, Some(7));
+
+        let config = XnsResolverBuilder::new()
+            .issuer(NamingService::XNS, "rNotAnAddress")
+            .config()
+            .clone();
+        assert!(matches!(config.validate(), Err(XnsError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_parse_toml_and_json() {
+        let toml = r#"
+            network = "testnet"
+            rpc_urls = ["http://localhost:5005"]
+            metadata_concurrency = 4
+            history_pages = 20
+            request_timeout_secs = 5
+            connect_timeout_secs = 2
+            services = ["xns"]
+
+            [metadata_pacing]
+            every = 5
+            delay_ms = 250
+
+            [cache]
+            ttl_secs = 60
+
+            [confusables]
+            indexed_capacity = 500
+
+            [custom_issuers]
+            xns = ["rYhfynZDrde1uSvvQAYctApg6DnVE5HKm"]
+        "#;
+        let config = ResolverConfig::from_toml_str(toml).unwrap();
+        assert_eq!(config.network, XrplNetwork::Testnet);
+        assert_eq!(config.cache.ttl, Duration::from_secs(60));
+        assert_eq!(config.cache.max_capacity, 1000);
+        assert_eq!(config.metadata_pacing.unwrap().delay, Duration::from_millis(250));
+        assert_eq!(config.history_pages, 20);
+        assert_eq!(config.confusables.indexed_capacity, 500);
+        assert!(config.validate().is_ok());
+
+        let unbounded = XnsResolverBuilder::from_config(config.clone()).indexed_confusables_capacity(0);
+        assert!(matches!(unbounded.config().validate(), Err(XnsError::ConfigError(_))));
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(ResolverConfig::from_json_str(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_custom_network() {
+        let toml = r#"
+            services = ["xns"]
+
+            [network.custom]
+            name = "local"
+            rpc_url = "http://localhost:5005"
+            ws_url = "ws://localhost:6006"
+            network_id = 21337
+
+            [issuers.local]
+            xns = ["rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh"]
+        "#;
+        let config = ResolverConfig::from_toml_str(toml).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.network.name(), "local");
+        assert_eq!(config.network.ws_url(), Some("ws://localhost:6006"));
+        assert_eq!(config.network.transaction_network_id(), Some(21337));
+        assert_eq!(config.effective_clio_url(), "http://localhost:5005");
+        assert_eq!(config.issuers_for(&NamingService::XNS).len(), 1);
+        assert!(config.issuers_for(&NamingService::XRPDomains).is_empty());
+        assert!(
+            ResolverConfig { services: vec![NamingService::XRPDomains], ..config.clone() }
+                .validate()
+                .is_err()
+        );
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(ResolverConfig::from_json_str(&json).unwrap(), config);
+
+        let config = ResolverConfig::new(XrplNetwork::Custom(CustomNetwork::new(
+            "mainnet",
+            "http://localhost:5005",
+        )));
+        assert!(matches!(config.validate(), Err(XnsError::ConfigError(_))));
+    }
+}
@@ -0,0 +1,185 @@
+//! Homograph screening of resolved names
+//!
+//! Names are compared by UTS-39 skeleton against an allowlist of protected
+//! names and, optionally, against the names the resolver has seen in naming
+//! service metadata, up to a configured number of the most recent. The skeleton of the name as the caller wrote it is
+//! checked as well as that of its normalized form, since normalization folds
+//! case and would hide look-alikes such as `ckeIley.xrp`.
+
+use crate::config::{ConfusableConfig, ConfusableMode};
+use crate::domain::{DomainName, skeleton};
+use crate::error::{XnsError, XnsResult};
+use crate::models::{DomainInfo, DomainWarning};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Mutex;
+
+type SkeletonIndex = HashMap<String, BTreeSet<DomainName>>;
+
+/// Names seen in metadata, with the order they were first seen in
+#[derive(Default)]
+struct SeenNames {
+    index: SkeletonIndex,
+    order: VecDeque<DomainName>,
+}
+
+/// Applies a [`ConfusableConfig`] to resolved names
+pub(crate) struct ConfusableScreen {
+    mode: ConfusableMode,
+    protected: SkeletonIndex,
+    check_indexed: bool,
+    indexed_capacity: usize,
+    indexed: Mutex<SeenNames>,
+}
+
+impl ConfusableScreen {
+    pub(crate) fn new(config: &ConfusableConfig) -> Self {
+        let mut protected = SkeletonIndex::new();
+        for name in config.allowlist.iter().filter_map(|name| DomainName::parse(name).ok()) {
+            protected.entry(name.skeleton()).or_default().insert(name);
+        }
+
+        Self {
+            mode: config.mode,
+            protected,
+            check_indexed: config.check_indexed,
+            indexed_capacity: config.indexed_capacity,
+            indexed: Mutex::new(SeenNames::default()),
+        }
+    }
+
+    /// Remember a name seen in naming service metadata, forgetting the
+    /// oldest once past capacity
+    pub(crate) fn index(&self, name: &DomainName) {
+        if !self.check_indexed || self.mode == ConfusableMode::Off {
+            return;
+        }
+
+        let mut indexed = self.indexed.lock().unwrap_or_else(|e| e.into_inner());
+        let seen = &mut *indexed;
+        if !seen.index.entry(name.skeleton()).or_default().insert(name.clone()) {
+            return;
+        }
+        seen.order.push_back(name.clone());
+
+        while seen.order.len() > self.indexed_capacity {
+            let Some(oldest) = seen.order.pop_front() else {
+                break;
+            };
+            let key = oldest.skeleton();
+            if let Some(names) = seen.index.get_mut(&key) {
+                names.remove(&oldest);
+                if names.is_empty() {
+                    seen.index.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Find reasons `name`, written as `input`, may impersonate another name
+    pub(crate) fn warnings(&self, input: &str, name: &DomainName) -> Vec<DomainWarning> {
+        if self.mode == ConfusableMode::Off {
+            return Vec::new();
+        }
+
+        let mut warnings = Vec::new();
+        if name.is_mixed_script() {
+            warnings.push(DomainWarning::MixedScript);
+        }
+
+        let written = skeleton(input.trim().trim_end_matches('.'));
+        let normalized = name.skeleton();
+        let indexed = self.indexed.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut lookalikes = BTreeSet::new();
+        for index in [Some(&self.protected), self.check_indexed.then_some(&indexed.index)]
+            .into_iter()
+            .flatten()
+        {
+            for key in [&written, &normalized] {
+                lookalikes.extend(index.get(key).into_iter().flatten().filter(|other| *other != name));
+            }
+        }
+
+        warnings.extend(
+            lookalikes
+                .into_iter()
+                .map(|other| DomainWarning::ConfusableWith(other.to_string())),
+        );
+        warnings
+    }
+
+    /// Attach warnings to a resolved domain, or refuse it
+    pub(crate) fn apply(
+        &self,
+        input: &str,
+        name: &DomainName,
+        mut info: DomainInfo,
+    ) -> XnsResult<DomainInfo> {
+        let warnings = self.warnings(input, name);
+
+        if self.mode == ConfusableMode::Refuse && !warnings.is_empty() {
+            let reasons: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+            return Err(XnsError::ConfusableDomain(format!("{} {}", name, reasons.join(", "))));
+        }
+
+        info.warnings = warnings;
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(mode: ConfusableMode) -> ConfusableScreen {
+        ConfusableScreen::new(&ConfusableConfig {
+            mode,
+            allowlist: vec!["ckelley.xrp".to_string()],
+            check_indexed: true,
+            indexed_capacity: 2,
+        })
+    }
+
+    #[test]
+    fn test_allowlist_lookalikes() {
+        let screen = screen(ConfusableMode::Flag);
+        let check = |input: &str| screen.warnings(input, &DomainName::parse(input).unwrap());
+
+        assert!(check("ckelley.xrp").is_empty());
+        assert!(check("CKELLEY.xrp").is_empty());
+        assert_eq!(check("ckeIley.xrp"), vec![DomainWarning::ConfusableWith("ckelley.xrp".to_string())]);
+        assert_eq!(
+            check("\u{441}k\u{435}lley.xrp"),
+            vec![
+                DomainWarning::MixedScript,
+                DomainWarning::ConfusableWith("ckelley.xrp".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indexed_lookalikes_refused() {
+        let screen = screen(ConfusableMode::Refuse);
+        let rn = DomainName::parse("rnexus.xrp").unwrap();
+        let m = DomainName::parse("mexus.xrp").unwrap();
+        screen.index(&rn);
+
+        assert!(screen.warnings("rnexus.xrp", &rn).is_empty());
+        let info = DomainInfo::test(m.as_str(), "rAlice");
+        assert!(matches!(screen.apply("mexus.xrp", &m, info), Err(XnsError::ConfusableDomain(_))));
+    }
+
+    #[test]
+    fn test_indexed_names_bounded() {
+        let screen = screen(ConfusableMode::Flag);
+        let name = |name: &str| DomainName::parse(name).unwrap();
+        for seen in ["rnexus.xrp", "alice.xrp", "alice.xrp", "bob.xrp"] {
+            screen.index(&name(seen));
+        }
+
+        // rnexus.xrp was the oldest of three names with room for two
+        assert!(screen.warnings("mexus.xrp", &name("mexus.xrp")).is_empty());
+        assert_eq!(screen.indexed.lock().unwrap().order, [name("alice.xrp"), name("bob.xrp")]);
+        assert_eq!(screen.indexed.lock().unwrap().index.len(), 2);
+    }
+}
@@ -0,0 +1,283 @@
+//! W3C DID documents for resolved names
+//!
+//! A resolved name is described by a DID Core document whose subject is its
+//! owner's `did:xrpl` identifier, `did:xrpl:<NetworkID>:<address>` as in
+//! XLS-40. The document lists:
+//!
+//! - the owner's account as its verification method, identified by a
+//!   CAIP-10 `blockchainAccountId` (`xrpl:0:r...` on mainnet);
+//! - the name in `alsoKnownAs`;
+//! - a service endpoint for each text record that links somewhere (`url`,
+//!   `email`, social handles, or any other record holding a URL);
+//! - the name's addresses on other chains, as further verification methods
+//!   that are not used for authentication, since nothing proves the owner
+//!   controls them. Chains without a known CAIP-2 identifier are left out.
+//!
+//! [`DidDocument::to_json_ld`] serializes it as JSON-LD, so the SDK can back
+//! a DID resolver driver.
+
+use crate::error::{XnsError, XnsResult};
+use crate::models::{DomainInfo, XrplNetwork};
+use serde::{Deserialize, Serialize};
+
+/// Base context of every DID document
+pub const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+
+/// Context defining `BlockchainVerificationMethod2021` and
+/// `blockchainAccountId`
+pub const BLOCKCHAIN_CONTEXT: &str = "https://w3id.org/security/suites/blockchain-2021/v1";
+
+const VERIFICATION_METHOD_TYPE: &str = "BlockchainVerificationMethod2021";
+
+/// CAIP-2 chain of linked addresses, by lowercase coin ticker
+const COIN_CHAINS: &[(&str, &str)] = &[
+    ("btc", "bip122:000000000019d6689c085ae165831e93"),
+    ("ltc", "bip122:12a765e31ffd4059bada1e25190f6e98"),
+    ("doge", "bip122:1a91e3dace36e2be3bf030a65679fe82"),
+    ("eth", "eip155:1"),
+    ("bnb", "eip155:56"),
+    ("matic", "eip155:137"),
+    ("pol", "eip155:137"),
+    ("avax", "eip155:43114"),
+    ("sol", "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp"),
+];
+
+/// Service type and profile URL prefix of text records holding a handle
+const HANDLE_SERVICES: &[(&str, &str, &str)] = &[
+    ("twitter", "Twitter", "https://x.com/"),
+    ("github", "GitHub", "https://github.com/"),
+    ("telegram", "Telegram", "https://t.me/"),
+    ("reddit", "Reddit", "https://www.reddit.com/user/"),
+    ("instagram", "Instagram", "https://www.instagram.com/"),
+    ("linkedin", "LinkedIn", "https://www.linkedin.com/in/"),
+];
+
+/// W3C DID Core document of a resolved name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+
+    /// The owner's `did:xrpl` identifier
+    pub id: String,
+
+    /// The resolved name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also_known_as: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verification_method: Vec<VerificationMethod>,
+
+    /// Verification methods that can authenticate as the subject
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authentication: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertion_method: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service: Vec<DidService>,
+}
+
+/// An account identified by its CAIP-10 `blockchainAccountId`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+    pub id: String,
+
+    #[serde(rename = "type")]
+    pub method_type: String,
+
+    pub controller: String,
+
+    pub blockchain_account_id: String,
+}
+
+/// A service endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidService {
+    pub id: String,
+
+    #[serde(rename = "type")]
+    pub service_type: String,
+
+    pub service_endpoint: String,
+}
+
+/// `did:xrpl` identifier of an account on a network
+///
+/// Fails for custom networks without a NetworkID.
+pub fn did_xrpl(network: &XrplNetwork, address: &str) -> XnsResult<String> {
+    let network_id = network.network_id().ok_or_else(|| {
+        XnsError::InvalidInput(format!("Network {} has no NetworkID for did:xrpl", network.name()))
+    })?;
+    Ok(format!("did:xrpl:{}:{}", network_id, address))
+}
+
+impl DidDocument {
+    /// Build the DID document of a resolved name on `network`
+    ///
+    /// Fails with `OwnerUnavailable` when the name's owner is unknown.
+    pub fn from_domain_info(info: &DomainInfo, network: &XrplNetwork) -> XnsResult<Self> {
+        let owner = info
+            .known_owner()
+            .ok_or_else(|| XnsError::OwnerUnavailable(format!("owner of {} is unknown", info.domain)))?;
+        // did_xrpl has already checked the NetworkID
+        let network_id = network.network_id().unwrap_or_default();
+        let id = did_xrpl(network, owner)?;
+
+        let owner_key = format!("{}#owner", id);
+        let mut verification_method = vec![VerificationMethod {
+            id: owner_key.clone(),
+            method_type: VERIFICATION_METHOD_TYPE.to_string(),
+            controller: id.clone(),
+            blockchain_account_id: format!("xrpl:{}:{}", network_id, owner),
+        }];
+
+        let mut addresses: Vec<_> = info.addresses.iter().collect();
+        addresses.sort();
+        verification_method.extend(addresses.into_iter().filter_map(|(coin, address)| {
+            let coin = coin.to_lowercase();
+            let chain = COIN_CHAINS.iter().find(|(ticker, _)| *ticker == coin)?.1;
+            Some(VerificationMethod {
+                id: format!("{}#{}", id, coin),
+                method_type: VERIFICATION_METHOD_TYPE.to_string(),
+                controller: id.clone(),
+                blockchain_account_id: format!("{}:{}", chain, address),
+            })
+        }));
+
+        let mut text_records: Vec<_> = info.text_records.iter().collect();
+        text_records.sort();
+        let service = text_records
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let (service_type, endpoint) = service_endpoint(key, value)?;
+                Some(DidService {
+                    id: format!("{}#{}", id, key),
+                    service_type,
+                    service_endpoint: endpoint,
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            context: vec![DID_CONTEXT.to_string(), BLOCKCHAIN_CONTEXT.to_string()],
+            id,
+            also_known_as: vec![info.domain.clone()],
+            verification_method,
+            authentication: vec![owner_key.clone()],
+            assertion_method: vec![owner_key],
+            service,
+        })
+    }
+
+    /// Serialize as JSON-LD
+    pub fn to_json_ld(&self) -> XnsResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Service type and endpoint of a text record, if it links somewhere
+fn service_endpoint(key: &str, value: &str) -> Option<(String, String)> {
+    let value = value.trim();
+    let is_url = value.starts_with("https://") || value.starts_with("http://");
+
+    match key {
+        "url" if is_url => Some(("LinkedDomains".to_string(), value.to_string())),
+        "email" if value.contains('@') => {
+            Some(("Email".to_string(), format!("mailto:{}", value.trim_start_matches("mailto:"))))
+        }
+        _ => {
+            if let Some((_, service_type, prefix)) = HANDLE_SERVICES.iter().find(|(k, _, _)| *k == key) {
+                let endpoint = if is_url {
+                    value.to_string()
+                } else if !value.is_empty() {
+                    format!("{}{}", prefix, value.trim_start_matches('@'))
+                } else {
+                    return None;
+                };
+                return Some((service_type.to_string(), endpoint));
+            }
+            // Any other record that is a link, e.g. `avatar`
+            (is_url && key != "description").then(|| (key.to_string(), value.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CustomNetwork, OwnerStatus};
+
+    fn info() -> DomainInfo {
+        let mut info = DomainInfo::test("ckelley.xrp", "rAlice");
+        info.addresses = [
+            ("BTC".to_string(), "bc1qalice".to_string()),
+            ("xyz".to_string(), "unknown-chain".to_string()),
+        ]
+        .into();
+        info.text_records = [
+            ("url", "https://ckelley.com"),
+            ("email", "c@ckelley.com"),
+            ("twitter", "@ckelley"),
+            ("avatar", "https://ckelley.com/a.png"),
+            ("description", "Hello"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        info
+    }
+
+    #[test]
+    fn test_did_document() {
+        let document = DidDocument::from_domain_info(&info(), &XrplNetwork::Mainnet).unwrap();
+        assert_eq!(document.id, "did:xrpl:0:rAlice");
+        assert_eq!(document.also_known_as, ["ckelley.xrp"]);
+        assert_eq!(document.authentication, ["did:xrpl:0:rAlice#owner"]);
+
+        let accounts: Vec<_> = document
+            .verification_method
+            .iter()
+            .map(|method| method.blockchain_account_id.as_str())
+            .collect();
+        assert_eq!(accounts, ["xrpl:0:rAlice", "bip122:000000000019d6689c085ae165831e93:bc1qalice"]);
+
+        let services: Vec<_> = document
+            .service
+            .iter()
+            .map(|s| (s.service_type.as_str(), s.service_endpoint.as_str()))
+            .collect();
+        assert_eq!(services, [
+            ("avatar", "https://ckelley.com/a.png"),
+            ("Email", "mailto:c@ckelley.com"),
+            ("Twitter", "https://x.com/ckelley"),
+            ("LinkedDomains", "https://ckelley.com"),
+        ]);
+
+        let json: serde_json::Value = serde_json::from_str(&document.to_json_ld().unwrap()).unwrap();
+        assert_eq!(json["@context"][0], DID_CONTEXT);
+        assert_eq!(json["verificationMethod"][0]["type"], "BlockchainVerificationMethod2021");
+        assert_eq!(json["service"][0]["serviceEndpoint"], "https://ckelley.com/a.png");
+    }
+
+    #[test]
+    fn test_did_document_needs_owner_and_network_id() {
+        let unknown = DomainInfo {
+            owner_status: OwnerStatus::Unknown,
+            ..info()
+        };
+        assert!(matches!(
+            DidDocument::from_domain_info(&unknown, &XrplNetwork::Mainnet),
+            Err(XnsError::OwnerUnavailable(_))
+        ));
+
+        let local = XrplNetwork::Custom(CustomNetwork::new("local", "http://localhost:5005"));
+        assert!(DidDocument::from_domain_info(&info(), &local).is_err());
+        let sidechain = XrplNetwork::Custom(CustomNetwork::new("side", "http://localhost:5005").with_network_id(21337));
+        assert_eq!(did_xrpl(&sidechain, "rAlice").unwrap(), "did:xrpl:21337:rAlice");
+    }
+}
@@ -0,0 +1,359 @@
+//! XLS-40 DID-based record storage
+//!
+//! Memo records have to be found by scanning an account's history. An
+//! account's DID is a single ledger object that can be read with one
+//! `ledger_entry` request, so records kept there are cheaper to resolve and
+//! are replaced rather than superseded.
+//!
+//! The DID's `Data`, `DIDDocument` and `URI` fields each hold up to 256 bytes.
+//! Any of them may carry a JSON [`DidRecords`] object using the memo types as
+//! keys, with the same schema as memo storage:
+//!
+//! ```json
+//! {"XNS_ADDRESSES": {"BTC": "bc1q..."}, "XNS_SUBDOMAINS": {"pay": {"address": "rPay..."}},
+//!  "XNS_PRIMARY_NAME": "ckelley.xrp"}
+//! ```
+//!
+//! Fields holding anything else, such as a URI pointing at a DID document,
+//! are skipped. When several fields hold records, `Data` wins over
+//! `DIDDocument`, which wins over `URI`.
+//!
+//! DID records are only honoured when the DID was last set after its owner
+//! acquired the name, like memo records.
+
+use crate::client::XrplClient;
+use crate::error::{XnsError, XnsResult};
+use crate::memo_storage::{
+    MemoStorage, SubdomainRecord, XNS_ADDRESSES_MEMO_TYPE, XNS_PRIMARY_NAME_MEMO_TYPE, XNS_SUBDOMAINS_MEMO_TYPE,
+};
+use crate::models::{DidObject, XrplNetwork};
+use crate::owner;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Maximum size of a DID field, in bytes before hex encoding
+pub const DID_FIELD_MAX_BYTES: usize = 256;
+
+/// Name records stored in a DID field, keyed by memo type
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DidRecords {
+    /// Addresses on other chains, as in an `XNS_ADDRESSES` memo
+    #[serde(rename = "XNS_ADDRESSES", default, skip_serializing_if = "HashMap::is_empty")]
+    pub addresses: HashMap<String, String>,
+
+    /// Subdomain records, as in an `XNS_SUBDOMAINS` memo
+    #[serde(rename = "XNS_SUBDOMAINS", default, skip_serializing_if = "HashMap::is_empty")]
+    pub subdomains: HashMap<String, SubdomainRecord>,
+
+    /// The account's primary name, as in an `XNS_PRIMARY_NAME` memo
+    #[serde(rename = "XNS_PRIMARY_NAME", default, skip_serializing_if = "Option::is_none")]
+    pub primary_name: Option<String>,
+}
+
+impl DidRecords {
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.subdomains.is_empty() && self.primary_name.is_none()
+    }
+
+    /// Add records from a lower-priority field, keeping existing keys
+    fn merge_under(&mut self, other: DidRecords) {
+        for (coin, address) in other.addresses {
+            self.addresses.entry(coin).or_insert(address);
+        }
+        for (label, record) in other.subdomains {
+            self.subdomains.entry(label).or_insert(record);
+        }
+        if self.primary_name.is_none() {
+            self.primary_name = other.primary_name;
+        }
+    }
+}
+
+/// Records read from an account's DID, with the ledger it was last set in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidEntry {
+    pub records: DidRecords,
+
+    pub ledger_index: Option<u32>,
+}
+
+/// An NFT owner's DID records, if set since acquiring the NFT
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnerDid {
+    /// The records, unless the DID was last set before the owner acquired
+    /// the NFT
+    pub entry: Option<DidEntry>,
+
+    /// Ledger in which the owner acquired the NFT, if it was within the
+    /// scanned history
+    pub acquired_ledger: Option<u32>,
+}
+
+/// DIDSet or DIDDelete transaction
+#[derive(Debug, Serialize)]
+pub struct DidTransaction {
+    #[serde(rename = "TransactionType")]
+    pub transaction_type: String,
+
+    #[serde(rename = "Account")]
+    pub account: String,
+
+    #[serde(rename = "URI", skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+
+    #[serde(rename = "Data", skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+
+    #[serde(rename = "DIDDocument", skip_serializing_if = "Option::is_none")]
+    pub did_document: Option<String>,
+
+    /// Required on chains whose NetworkID is above 1024
+    #[serde(rename = "NetworkID", skip_serializing_if = "Option::is_none")]
+    pub network_id: Option<u32>,
+}
+
+impl DidTransaction {
+    /// Create a DIDSet transaction storing records in the account's DID
+    ///
+    /// Records go to `Data`; when they do not fit, subdomains are moved to
+    /// `DIDDocument`. Fails when they still exceed 256 bytes per field.
+    /// Records that fit in `Data` clear `DIDDocument`, so subdomains left
+    /// there by an earlier, larger set are not read back.
+    pub fn set(account: String, records: &DidRecords) -> XnsResult<Self> {
+        let mut tx = Self::new("DIDSet", account);
+
+        let all = Self::encode(records)?;
+        if all.len() <= DID_FIELD_MAX_BYTES * 2 {
+            tx.data = Some(all);
+            tx.did_document = Some(String::new());
+            return Ok(tx);
+        }
+
+        let addresses = DidRecords {
+            addresses: records.addresses.clone(),
+            primary_name: records.primary_name.clone(),
+            ..Default::default()
+        };
+        let subdomains = DidRecords {
+            subdomains: records.subdomains.clone(),
+            ..Default::default()
+        };
+        let data = Self::encode(&addresses)?;
+        let did_document = Self::encode(&subdomains)?;
+        if data.len() > DID_FIELD_MAX_BYTES * 2 || did_document.len() > DID_FIELD_MAX_BYTES * 2 {
+            return Err(XnsError::InvalidInput(format!(
+                "Records do not fit in DID fields of {} bytes",
+                DID_FIELD_MAX_BYTES
+            )));
+        }
+        tx.data = Some(data);
+        tx.did_document = Some(did_document);
+        Ok(tx)
+    }
+
+    /// Create a DIDDelete transaction removing the account's DID
+    pub fn delete(account: String) -> Self {
+        Self::new("DIDDelete", account)
+    }
+
+    fn new(transaction_type: &str, account: String) -> Self {
+        Self {
+            transaction_type: transaction_type.to_string(),
+            account,
+            uri: None,
+            data: None,
+            did_document: None,
+            network_id: None,
+        }
+    }
+
+    /// Hex-encoded JSON of records
+    fn encode(records: &DidRecords) -> XnsResult<String> {
+        let json = serde_json::to_string(records)
+            .map_err(|e| XnsError::InvalidInput(format!("Failed to serialize DID records: {}", e)))?;
+        Ok(hex::encode_upper(json.as_bytes()))
+    }
+
+    /// Set the DID's `URI`, e.g. a link to a full DID document
+    pub fn with_uri(mut self, uri: &str) -> XnsResult<Self> {
+        if uri.len() > DID_FIELD_MAX_BYTES {
+            return Err(XnsError::InvalidInput(format!(
+                "DID URI is longer than {} bytes",
+                DID_FIELD_MAX_BYTES
+            )));
+        }
+        self.uri = Some(hex::encode_upper(uri.as_bytes()));
+        Ok(self)
+    }
+
+    /// Set the `NetworkID` the transaction is bound to
+    pub fn for_network(mut self, network: &XrplNetwork) -> Self {
+        self.network_id = network.transaction_network_id();
+        self
+    }
+}
+
+/// DID storage handler
+pub struct DidStorage {
+    client: XrplClient,
+}
+
+impl DidStorage {
+    /// Create a new DID storage handler
+    pub fn new(client: XrplClient) -> Self {
+        Self { client }
+    }
+
+    /// Build an unsigned DIDSet transaction storing records
+    pub fn build_set_transaction(&self, account: &str, records: &DidRecords) -> XnsResult<String> {
+        let tx = DidTransaction::set(account.to_string(), records)?.for_network(&self.client.network());
+        serde_json::to_string_pretty(&tx)
+            .map_err(|e| XnsError::InvalidInput(format!("Failed to serialize transaction: {}", e)))
+    }
+
+    /// Build an unsigned DIDDelete transaction
+    pub fn build_delete_transaction(&self, account: &str) -> XnsResult<String> {
+        let tx = DidTransaction::delete(account.to_string()).for_network(&self.client.network());
+        serde_json::to_string_pretty(&tx)
+            .map_err(|e| XnsError::InvalidInput(format!("Failed to serialize transaction: {}", e)))
+    }
+
+    /// Records in `account`'s DID, or `None` if it has no DID
+    pub async fn get_records(&self, account: &str) -> XnsResult<Option<DidEntry>> {
+        Ok(self.client.did(account).await?.map(|did| DidEntry {
+            records: Self::parse_did(&did),
+            ledger_index: did.previous_txn_lgr_seq,
+        }))
+    }
+
+    /// Records in `owner`'s DID, if it was set since the owner acquired
+    /// `nft_id`
+    ///
+    /// History is only scanned for the acquisition when the owner has a DID.
+    pub async fn owner_records(&self, owner: &str, nft_id: &str) -> XnsResult<OwnerDid> {
+        let Some(entry) = self.get_records(owner).await? else {
+            return Ok(OwnerDid::default());
+        };
+        let acquired_ledger = owner::acquired_ledger(&self.client, owner, nft_id).await?;
+
+        let current = match (entry.ledger_index, acquired_ledger) {
+            (Some(set), Some(acquired)) => set >= acquired,
+            _ => true,
+        };
+        Ok(OwnerDid {
+            entry: current.then_some(entry),
+            acquired_ledger,
+        })
+    }
+
+    /// Records in a DID's fields, `Data` first
+    pub fn parse_did(did: &DidObject) -> DidRecords {
+        let mut records = DidRecords::default();
+
+        for field in [&did.data, &did.did_document, &did.uri].into_iter().flatten() {
+            if let Some(found) = Self::parse_field(field) {
+                records.merge_under(found);
+            }
+        }
+        records
+    }
+
+    /// Records in one hex-encoded field, if it holds a records object
+    fn parse_field(field_hex: &str) -> Option<DidRecords> {
+        let json = MemoStorage::decode_memo(field_hex).ok()?;
+        let value: serde_json::Value = serde_json::from_str(&json).ok()?;
+        let object = value.as_object()?;
+        if ![XNS_ADDRESSES_MEMO_TYPE, XNS_SUBDOMAINS_MEMO_TYPE, XNS_PRIMARY_NAME_MEMO_TYPE]
+            .iter()
+            .any(|key| object.contains_key(*key))
+        {
+            return None;
+        }
+        serde_json::from_value(value).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(subdomains: usize) -> DidRecords {
+        DidRecords {
+            addresses: [("BTC".to_string(), "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh".to_string())].into(),
+            subdomains: (0..subdomains)
+                .map(|i| {
+                    let record = SubdomainRecord {
+                        address: Some("rPEPPER7kfTD9w2To4CQk6UCfuHM9c6GDY".to_string()),
+                        ..Default::default()
+                    };
+                    (format!("sub{}", i), record)
+                })
+                .collect(),
+            primary_name: None,
+        }
+    }
+
+    #[test]
+    fn test_build_did_set() {
+        let tx = DidTransaction::set("rAlice".to_string(), &records(1)).unwrap();
+        assert_eq!(tx.transaction_type, "DIDSet");
+        assert_eq!(tx.did_document.as_deref(), Some(""));
+        assert_eq!(serde_json::to_value(&tx).unwrap()["DIDDocument"], "");
+
+        let did = DidObject {
+            data: tx.data.clone(),
+            ..Default::default()
+        };
+        assert_eq!(DidStorage::parse_did(&did), records(1));
+
+        // Too big for Data alone: subdomains move to DIDDocument
+        let tx = DidTransaction::set("rAlice".to_string(), &records(4)).unwrap();
+        assert!(tx.did_document.is_some());
+        let did = DidObject {
+            data: tx.data,
+            did_document: tx.did_document,
+            ..Default::default()
+        };
+        assert_eq!(DidStorage::parse_did(&did), records(4));
+
+        // Shrinking back into Data clears the stale subdomains
+        let tx = DidTransaction::set("rAlice".to_string(), &records(1)).unwrap();
+        let did = DidObject {
+            data: tx.data,
+            did_document: tx.did_document.filter(|field| !field.is_empty()),
+            ..did
+        };
+        assert_eq!(DidStorage::parse_did(&did), records(1));
+
+        assert!(DidTransaction::set("rAlice".to_string(), &records(20)).is_err());
+        let delete = serde_json::to_value(DidTransaction::delete("rAlice".to_string())).unwrap();
+        assert_eq!(delete, serde_json::json!({ "TransactionType": "DIDDelete", "Account": "rAlice" }));
+    }
+
+    #[test]
+    fn test_parse_did_fields() {
+        let encode = |text: &str| Some(hex::encode_upper(text.as_bytes()));
+        let did = DidObject {
+            uri: encode("https://example.com/did.json"),
+            data: encode(r#"{"XNS_ADDRESSES":{"BTC":"bc1-data"}}"#),
+            did_document: encode(r#"{"XNS_ADDRESSES":{"BTC":"bc1-doc","ETH":"0xdoc"}}"#),
+            ..Default::default()
+        };
+
+        let records = DidStorage::parse_did(&did);
+        assert_eq!(records.addresses["BTC"], "bc1-data");
+        assert_eq!(records.addresses["ETH"], "0xdoc");
+
+        let primary = DidObject {
+            uri: encode(r#"{"XNS_PRIMARY_NAME":"ckelley.xrp"}"#),
+            ..Default::default()
+        };
+        assert_eq!(DidStorage::parse_did(&primary).primary_name.as_deref(), Some("ckelley.xrp"));
+
+        let document = DidObject {
+            did_document: encode(r#"{"@context":"https://www.w3.org/ns/did/v1"}"#),
+            ..Default::default()
+        };
+        assert!(DidStorage::parse_did(&document).is_empty());
+    }
+}
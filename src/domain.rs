@@ -0,0 +1,304 @@
+//! Domain name normalization and validation
+//!
+//! [`DomainName`] is the canonical form of a name such as `ckelley.xrp`.
+//! Any TLD is accepted here; which TLDs resolve is decided by the configured
+//! naming service providers. Parsing trims
+//! surrounding whitespace and a trailing root dot, applies UTS-46 processing
+//! (case folding, NFC normalization and mapping of compatibility characters)
+//! and converts internationalized labels to punycode, so `CKELLEY.XRP`,
+//! `" ckelley.xrp"` and `ckelley.xrp.` all parse to the same name. The
+//! resolver and the metadata parser compare names only in this form.
+//!
+//! Normalization does not make look-alike names equal: `ckeIley.xrp` (capital
+//! I) and Cyrillic `сkelley.xrp` are distinct, valid names. [`skeleton`] and
+//! [`DomainName::is_mixed_script`] implement the UTS-39 checks used to spot
+//! them.
+
+use crate::error::{XnsError, XnsResult};
+use idna::AsciiDenyList;
+use idna::uts46::{DnsLength, Hyphens, Uts46};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use unicode_security::MixedScript;
+
+/// Top-level label of names served by the built-in naming services
+pub const XRP_TLD: &str = "xrp";
+
+/// Longest label allowed by DNS
+const MAX_LABEL_LEN: usize = 63;
+
+/// Longest name allowed by DNS, excluding the root dot
+const MAX_NAME_LEN: usize = 253;
+
+/// UTS-39 confusable skeleton of a string
+///
+/// Two strings with the same skeleton are visually confusable. The skeleton
+/// is case-sensitive: pass text as it was written, before normalization, to
+/// catch look-alikes that rely on letter case.
+pub fn skeleton(text: &str) -> String {
+    unicode_security::skeleton(text).collect()
+}
+
+/// Normalize a TLD (`.XRP` → `xrp`) and check it is a valid label
+pub fn normalize_tld(tld: &str) -> XnsResult<String> {
+    DomainName::parse(&format!("x.{}", tld.trim().trim_start_matches('.')))
+        .ok()
+        .filter(|name| !name.is_subdomain())
+        .map(|name| name.tld().to_string())
+        .ok_or_else(|| XnsError::InvalidInput(format!("Invalid TLD: {}", tld)))
+}
+
+/// A validated, normalized domain name
+///
+/// The name is stored in its ASCII form: lowercase, with internationalized
+/// labels as punycode (`xn--...`). Use [`DomainName::to_unicode`] for
+/// display.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DomainName {
+    ascii: String,
+}
+
+impl DomainName {
+    /// Parse and normalize a domain name
+    pub fn parse(input: &str) -> XnsResult<Self> {
+        let invalid = |reason: &str| XnsError::InvalidDomain(format!("{}: {}", input, reason));
+
+        let trimmed = input.trim();
+        let trimmed = trimmed.strip_suffix('.').unwrap_or(trimmed);
+
+        if trimmed.is_empty() {
+            return Err(invalid("empty name"));
+        }
+        if trimmed.split('.').any(str::is_empty) {
+            return Err(invalid("empty label"));
+        }
+
+        let ascii = Uts46::new()
+            .to_ascii(
+                trimmed.as_bytes(),
+                AsciiDenyList::EMPTY,
+                Hyphens::Allow,
+                DnsLength::Ignore,
+            )
+            .map_err(|_| invalid("not a valid internationalized name"))?
+            .into_owned();
+
+        if ascii.len() > MAX_NAME_LEN {
+            return Err(invalid("name is longer than 253 characters"));
+        }
+
+        for label in ascii.split('.') {
+            Self::check_label(label).map_err(|reason| invalid(&reason))?;
+        }
+
+        let name = Self { ascii };
+        if name.labels().count() < 2 {
+            return Err(invalid("domain must have a name and a TLD"));
+        }
+        if name.tld().bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid("TLD cannot be numeric"));
+        }
+
+        Ok(name)
+    }
+
+    /// Check an ASCII label against DNS hostname rules
+    fn check_label(label: &str) -> Result<(), String> {
+        if label.is_empty() {
+            return Err("empty label".to_string());
+        }
+        if label.len() > MAX_LABEL_LEN {
+            return Err(format!("label '{}' is longer than 63 characters", label));
+        }
+        if let Some(c) = label.chars().find(|c| !c.is_ascii_alphanumeric() && *c != '-') {
+            return Err(format!("label '{}' contains '{}'", label, c));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!("label '{}' starts or ends with a hyphen", label));
+        }
+        if label.get(2..4) == Some("--") && !label.starts_with("xn--") {
+            return Err(format!("label '{}' has hyphens in the third and fourth positions", label));
+        }
+
+        Ok(())
+    }
+
+    /// ASCII form of the name
+    pub fn as_str(&self) -> &str {
+        &self.ascii
+    }
+
+    /// Name with punycode labels decoded, for display
+    pub fn to_unicode(&self) -> String {
+        idna::domain_to_unicode(&self.ascii).0
+    }
+
+    /// Labels from left to right
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.ascii.split('.')
+    }
+
+    /// Everything before the top-level label (e.g. `ckelley` for
+    /// `ckelley.xrp`)
+    pub fn name(&self) -> &str {
+        self.ascii
+            .rsplit_once('.')
+            .map_or(self.ascii.as_str(), |(name, _)| name)
+    }
+
+    /// Top-level label (`xrp` for `ckelley.xrp`)
+    pub fn tld(&self) -> &str {
+        self.ascii
+            .rsplit_once('.')
+            .map_or(self.ascii.as_str(), |(_, tld)| tld)
+    }
+
+    /// Whether the name has labels below the registered name, like
+    /// `pay.ckelley.xrp`
+    pub fn is_subdomain(&self) -> bool {
+        self.labels().count() > 2
+    }
+
+    /// The registered name that owns this one (`ckelley.xrp` for
+    /// `pay.ckelley.xrp`, or the name itself)
+    pub fn registered(&self) -> DomainName {
+        let mut labels: Vec<&str> = self.labels().collect();
+        let registered = labels.split_off(labels.len().saturating_sub(2));
+        Self { ascii: registered.join(".") }
+    }
+
+    /// Labels below the registered name (`pay` for `pay.ckelley.xrp`)
+    pub fn subdomain(&self) -> Option<&str> {
+        let registered_len = self.registered().ascii.len();
+        self.ascii
+            .get(..self.ascii.len() - registered_len)
+            .and_then(|prefix| prefix.strip_suffix('.'))
+    }
+
+    /// Whether any label is internationalized
+    pub fn is_idn(&self) -> bool {
+        self.labels().any(|label| label.starts_with("xn--"))
+    }
+
+    /// Confusable skeleton of the Unicode form
+    pub fn skeleton(&self) -> String {
+        skeleton(&self.to_unicode())
+    }
+
+    /// Whether any label below the TLD mixes scripts, e.g. Latin and
+    /// Cyrillic letters in one label
+    pub fn is_mixed_script(&self) -> bool {
+        let unicode = self.to_unicode();
+        let name = unicode.rsplit_once('.').map_or(unicode.as_str(), |(name, _)| name);
+        name.split('.').any(|label| !label.is_single_script())
+    }
+}
+
+impl fmt::Display for DomainName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.ascii)
+    }
+}
+
+impl FromStr for DomainName {
+    type Err = XnsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for DomainName {
+    type Error = XnsError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<DomainName> for String {
+    fn from(name: DomainName) -> Self {
+        name.ascii
+    }
+}
+
+impl AsRef<str> for DomainName {
+    fn as_ref(&self) -> &str {
+        &self.ascii
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confusables() {
+        let real = DomainName::parse("ckelley.xrp").unwrap();
+
+        // Capital I only looks like l before normalization
+        assert_eq!(skeleton("ckeIley.xrp"), real.skeleton());
+        assert_ne!(DomainName::parse("ckeIley.xrp").unwrap(), real);
+
+        // Cyrillic с and е among Latin letters
+        let spoof = DomainName::parse("\u{441}k\u{435}lley.xrp").unwrap();
+        assert_ne!(spoof, real);
+        assert_eq!(spoof.skeleton(), real.skeleton());
+        assert!(spoof.is_mixed_script());
+        assert!(!real.is_mixed_script());
+    }
+
+    #[test]
+    fn test_normalization() {
+        for input in ["ckelley.xrp", "CKELLEY.XRP", " ckelley.xrp\n", "ckelley.xrp.", "ｃｋｅｌｌｅｙ.xrp"] {
+            assert_eq!(DomainName::parse(input).unwrap().as_str(), "ckelley.xrp", "{:?}", input);
+        }
+
+        let idn = DomainName::parse("Café.xrp").unwrap();
+        assert_eq!(idn.as_str(), "xn--caf-dma.xrp");
+        assert_eq!(idn.to_unicode(), "café.xrp");
+        assert!(idn.is_idn());
+        // Decomposed input normalizes to the same name
+        assert_eq!(DomainName::parse("cafe\u{301}.xrp").unwrap(), idn);
+    }
+
+    #[test]
+    fn test_validation() {
+        for input in [
+            "",
+            "xrp",
+            "ckelley.123",
+            "ckelley..xrp",
+            ".ckelley.xrp",
+            "-ckelley.xrp",
+            "ckelley-.xrp",
+            "ck--elley.xrp",
+            "ck_kelley.xrp",
+            "ck kelley.xrp",
+            &format!("{}.xrp", "a".repeat(64)),
+        ] {
+            assert!(
+                matches!(DomainName::parse(input), Err(XnsError::InvalidDomain(_))),
+                "{:?}",
+                input
+            );
+        }
+
+        let sub = DomainName::parse("a.Pay.ckelley.xrp").unwrap();
+        assert!(!sub.is_mixed_script());
+        assert!(sub.is_subdomain());
+        assert_eq!(sub.registered().as_str(), "ckelley.xrp");
+        assert_eq!(sub.subdomain(), Some("a.pay"));
+        assert_eq!(sub.registered().subdomain(), None);
+        assert_eq!(sub.name(), "a.pay.ckelley");
+        assert_eq!(sub.tld(), "xrp");
+
+        let other = DomainName::parse("Ape.XRPL").unwrap();
+        assert_eq!(other.tld(), "xrpl");
+        assert!(!other.is_subdomain());
+        assert_eq!(normalize_tld(".XRPL").unwrap(), "xrpl");
+        assert!(normalize_tld("x.rp").is_err());
+    }
+}
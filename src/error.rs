@@ -2,7 +2,7 @@ use thiserror::Error;
 
 pub type XnsResult<T> = Result<T, XnsError>;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum XnsError {
     #[error("Domain not found: {0}")]
     DomainNotFound(String),
@@ -25,6 +25,21 @@ pub enum XnsError {
     #[error("Unsupported naming service: {0}")]
     UnsupportedService(String),
 
+    #[error("Domain registered with several naming services: {0}")]
+    AmbiguousDomain(String),
+
+    #[error("Domain expired: {0}")]
+    DomainExpired(String),
+
+    #[error("Confusable domain: {0}")]
+    ConfusableDomain(String),
+
+    #[error("NFT owner unavailable: {0}")]
+    OwnerUnavailable(String),
+
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
+
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
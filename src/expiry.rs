@@ -0,0 +1,312 @@
+//! Domain expiration
+//!
+//! Naming services record when a name lapses in its NFT metadata, either as
+//! an attribute (`{"trait_type": "Expiration Date", "value": ...}`) or as a
+//! top-level field such as `expires_at`. Values may be unix timestamps in
+//! seconds or milliseconds, or dates: ISO 8601 (`2025-11-15`,
+//! `2025-11-15T08:00:00Z`), `2025/11/15`, `Nov 15, 2025` or `15 Nov 2025`.
+//! Dates without a time are read as midnight UTC.
+//!
+//! The resolver reports each name's [`ExpiryStatus`] and, depending on the
+//! configured [`ExpiryMode`], refuses names past their grace period.
+
+use crate::config::{ExpiryConfig, ExpiryMode};
+use crate::error::{XnsError, XnsResult};
+use crate::models::{DomainInfo, ExpiryStatus, NftMetadata, unix_now};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Metadata keys holding the expiration, compared lowercase without
+/// separators
+const EXPIRY_KEYS: &[&str] = &[
+    "expiration",
+    "expirationdate",
+    "expirationtime",
+    "expirationtimestamp",
+    "expire",
+    "expiredate",
+    "expires",
+    "expiresat",
+    "expiry",
+    "expirydate",
+    "validuntil",
+];
+
+/// Timestamps above this are taken to be in milliseconds (year 5138 in
+/// seconds)
+const MILLIS_THRESHOLD: u64 = 100_000_000_000;
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+/// Expiration of a name, as a unix timestamp, from its NFT metadata
+///
+/// Attributes are checked before top-level fields; unparseable values are
+/// skipped.
+pub fn parse_expiry(metadata: &NftMetadata) -> Option<u64> {
+    let attributes = metadata
+        .attributes
+        .iter()
+        .map(|attribute| (attribute.trait_type.as_str(), &attribute.value));
+    let fields = metadata.extra.iter().map(|(key, value)| (key.as_str(), value));
+
+    attributes
+        .chain(fields)
+        .filter(|(key, _)| is_expiry_key(key))
+        .find_map(|(_, value)| parse_timestamp(value))
+}
+
+fn is_expiry_key(key: &str) -> bool {
+    let key: String = key
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    EXPIRY_KEYS.contains(&key.as_str())
+}
+
+/// Unix timestamp from a number or a date string
+pub fn parse_timestamp(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .or_else(|| number.as_f64().filter(|n| *n >= 0.0).map(|n| n as u64))
+            .map(from_epoch_number),
+        Value::String(text) => parse_date(text.trim()),
+        _ => None,
+    }
+}
+
+fn from_epoch_number(n: u64) -> u64 {
+    if n > MILLIS_THRESHOLD { n / 1000 } else { n }
+}
+
+fn parse_date(text: &str) -> Option<u64> {
+    if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+        return text.parse().ok().map(from_epoch_number);
+    }
+    parse_iso(text).or_else(|| parse_named_month(text))
+}
+
+/// `YYYY-MM-DD` or `YYYY/MM/DD`, optionally followed by `T` or a space, a
+/// time and a UTC offset
+fn parse_iso(text: &str) -> Option<u64> {
+    let (date, time) = match text.find(['T', 't', ' ']) {
+        Some(idx) => (&text[..idx], Some(&text[idx + 1..])),
+        None => (text, None),
+    };
+
+    let mut parts = date.split(['-', '/']);
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day)?;
+    let seconds = match time {
+        Some(time) => parse_time(time)?,
+        None => 0,
+    };
+    u64::try_from(days * 86_400 + seconds).ok()
+}
+
+/// `HH:MM[:SS[.fff]]` with an optional `Z` or `±HH[:]MM` offset, as seconds
+/// from midnight UTC
+fn parse_time(time: &str) -> Option<i64> {
+    let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else if let Some(idx) = time.rfind(['+', '-']) {
+        let sign = if time[idx..].starts_with('-') { -1 } else { 1 };
+        let offset = time[idx + 1..].replace(':', "");
+        if offset.len() != 4 {
+            return None;
+        }
+        let hours: i64 = offset[..2].parse().ok()?;
+        let minutes: i64 = offset[2..].parse().ok()?;
+        (&time[..idx], sign * (hours * 3600 + minutes * 60))
+    } else {
+        (time.trim_end_matches(" UTC"), 0)
+    };
+
+    let clock = clock.split('.').next()?;
+    let mut fields = clock.split(':');
+    let hours: i64 = fields.next()?.parse().ok()?;
+    let minutes: i64 = fields.next()?.parse().ok()?;
+    let seconds: i64 = fields.next().map_or(Some(0), |s| s.parse().ok())?;
+    if hours > 23 || minutes > 59 || seconds > 60 || fields.next().is_some() {
+        return None;
+    }
+
+    Some(hours * 3600 + minutes * 60 + seconds - offset)
+}
+
+/// `Nov 15, 2025`, `November 15 2025` or `15 Nov 2025`
+fn parse_named_month(text: &str) -> Option<u64> {
+    let words: Vec<&str> = text
+        .split([' ', ','])
+        .filter(|word| !word.is_empty())
+        .collect();
+    let [first, second, year] = words.as_slice() else {
+        return None;
+    };
+
+    let (month, day) = match month_number(first) {
+        Some(month) => (month, second),
+        None => (month_number(second)?, first),
+    };
+    let day = day.trim_end_matches(|c: char| c.is_ascii_alphabetic()).parse().ok()?;
+
+    let days = days_from_civil(year.parse().ok()?, month, day)?;
+    u64::try_from(days * 86_400).ok()
+}
+
+fn month_number(word: &str) -> Option<u32> {
+    let word = word.to_ascii_lowercase();
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| word.starts_with(month))
+        .map(|idx| idx as u32 + 1)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day == 0 || day > days_in_month {
+        return None;
+    }
+
+    // Howard Hinnant's days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// Applies an [`ExpiryConfig`] to resolved names
+#[derive(Debug, Clone)]
+pub(crate) struct ExpiryCheck {
+    mode: ExpiryMode,
+    grace_period: Duration,
+}
+
+impl ExpiryCheck {
+    pub(crate) fn new(config: &ExpiryConfig) -> Self {
+        Self {
+            mode: config.mode,
+            grace_period: config.grace_period,
+        }
+    }
+
+    /// Status of a name expiring at `expires_at`, as of `now`
+    pub(crate) fn status(&self, expires_at: Option<u64>, now: u64) -> ExpiryStatus {
+        match expires_at {
+            Some(expires_at) if now >= expires_at.saturating_add(self.grace_period.as_secs()) => {
+                ExpiryStatus::Expired
+            }
+            Some(expires_at) if now >= expires_at => ExpiryStatus::GracePeriod,
+            _ => ExpiryStatus::Active,
+        }
+    }
+
+    /// Whether a registration is refused as expired, so that another
+    /// service's registration of the name can be used instead
+    pub(crate) fn refuses(&self, info: &DomainInfo) -> bool {
+        self.mode == ExpiryMode::Enforce && self.status(info.expires_at, unix_now()) == ExpiryStatus::Expired
+    }
+
+    /// Record a resolved name's expiry status, or refuse it
+    ///
+    /// Names in their grace period always resolve.
+    pub(crate) fn apply(&self, mut info: DomainInfo) -> XnsResult<DomainInfo> {
+        info.expiry = match self.mode {
+            ExpiryMode::Ignore => ExpiryStatus::Active,
+            _ => self.status(info.expires_at, unix_now()),
+        };
+
+        if self.mode == ExpiryMode::Enforce && info.expiry == ExpiryStatus::Expired {
+            return Err(XnsError::DomainExpired(info.domain));
+        }
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MetadataAttribute;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_timestamp_formats() {
+        // 2025-11-15T00:00:00Z
+        let midnight = 1_763_164_800;
+        for value in [
+            json!(midnight),
+            json!(midnight * 1000),
+            json!("1763164800"),
+            json!("2025-11-15"),
+            json!("2025/11/15"),
+            json!("2025-11-15T00:00:00Z"),
+            json!("2025-11-15T02:00:00.500+02:00"),
+            json!("2025-11-14 19:00:00-0500"),
+            json!("Nov 15, 2025"),
+            json!("15 November 2025"),
+        ] {
+            assert_eq!(parse_timestamp(&value), Some(midnight), "{}", value);
+        }
+
+        for value in [json!("2025-02-30"), json!("soon"), json!(-1), json!(null)] {
+            assert_eq!(parse_timestamp(&value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_parse_expiry_from_metadata() {
+        let metadata: NftMetadata = serde_json::from_value(json!({
+            "name": "ckelley.xrp",
+            "attributes": [
+                { "trait_type": "Length", "value": 7 },
+                { "trait_type": "Expiration Date", "value": "2030-01-01" }
+            ],
+            "expires_at": 0
+        }))
+        .unwrap();
+        assert_eq!(parse_expiry(&metadata), Some(1_893_456_000));
+
+        let metadata = NftMetadata {
+            attributes: vec![MetadataAttribute {
+                trait_type: "Expiry".to_string(),
+                value: json!("unknown"),
+            }],
+            extra: [("validUntil".to_string(), json!(1_893_456_000))].into(),
+            ..metadata
+        };
+        assert_eq!(parse_expiry(&metadata), Some(1_893_456_000));
+    }
+
+    #[test]
+    fn test_status_with_grace_period() {
+        let check = ExpiryCheck::new(&ExpiryConfig {
+            mode: ExpiryMode::Enforce,
+            grace_period: Duration::from_secs(100),
+        });
+
+        assert_eq!(check.status(None, 1_000), ExpiryStatus::Active);
+        assert_eq!(check.status(Some(1_000), 999), ExpiryStatus::Active);
+        assert_eq!(check.status(Some(1_000), 1_000), ExpiryStatus::GracePeriod);
+        assert_eq!(check.status(Some(1_000), 1_100), ExpiryStatus::Expired);
+    }
+}
@@ -0,0 +1,237 @@
+//! Naming service issuer registry
+//!
+//! Each naming service mints its name NFTs from one or more issuer accounts,
+//! sometimes under a specific NFToken taxon. [`IssuerRegistry`] records them
+//! per network, so the same configuration can point at mainnet issuers, at
+//! testnet, or at test names minted on a local network.
+//!
+//! ```toml
+//! [issuers.testnet]
+//! xns = ["rYhfynZDrde1uSvvQAYctApg6DnVE5HKm"]
+//!
+//! [issuers.devnet]
+//! xns = [{ address = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh", taxon = 7 }]
+//! ```
+
+use crate::address::{decode_account_id, nft_issuer_id, nft_taxon};
+use crate::error::{XnsError, XnsResult};
+use crate::models::{NamingService, XrplNetwork, XrplNft};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// An account that mints a naming service's name NFTs
+///
+/// Deserializes from an address, or from a table with an `address` and an
+/// optional `taxon`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "IssuerSpec")]
+pub struct Issuer {
+    pub address: String,
+
+    /// Only NFTs minted under this taxon are names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taxon: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IssuerSpec {
+    Address(String),
+    Full {
+        address: String,
+        #[serde(default)]
+        taxon: Option<u32>,
+    },
+}
+
+impl From<IssuerSpec> for Issuer {
+    fn from(spec: IssuerSpec) -> Self {
+        match spec {
+            IssuerSpec::Address(address) => Self { address, taxon: None },
+            IssuerSpec::Full { address, taxon } => Self { address, taxon },
+        }
+    }
+}
+
+impl Issuer {
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            taxon: None,
+        }
+    }
+
+    /// Only accept NFTs minted under `taxon`
+    pub fn with_taxon(mut self, taxon: u32) -> Self {
+        self.taxon = Some(taxon);
+        self
+    }
+
+    /// Whether an NFT was minted by this issuer, under its taxon if set
+    ///
+    /// Accounts are compared by account ID, which the NFTokenID embeds.
+    pub fn matches(&self, nft: &XrplNft) -> bool {
+        let minted_by = nft_issuer_id(&nft.nft_token_id);
+        if minted_by.is_none() || minted_by != decode_account_id(&self.address).ok() {
+            return false;
+        }
+
+        match self.taxon {
+            Some(taxon) => nft.taxon.or_else(|| nft_taxon(&nft.nft_token_id)) == Some(taxon),
+            None => true,
+        }
+    }
+}
+
+impl From<&str> for Issuer {
+    fn from(address: &str) -> Self {
+        Self::new(address)
+    }
+}
+
+impl From<String> for Issuer {
+    fn from(address: String) -> Self {
+        Self::new(address)
+    }
+}
+
+/// Issuers of each naming service, per network
+///
+/// Networks are keyed by [`XrplNetwork::name`], so a custom network's
+/// issuers are listed under its name.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IssuerRegistry {
+    networks: HashMap<String, HashMap<NamingService, Vec<Issuer>>>,
+}
+
+impl IssuerRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issuers of the built-in naming services
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        // XNS (xrpns.com) - Verified from ckelley.xrp NFT
+        registry.add(
+            &XrplNetwork::Mainnet,
+            NamingService::XNS,
+            "rYhfynZDrde1uSvvQAYctApg6DnVE5HKm",
+        );
+        // XRP Domains (xrpdomains.xyz) - From xrp.cafe research
+        registry.add(
+            &XrplNetwork::Mainnet,
+            NamingService::XRPDomains,
+            "r4pM3nT7r7X1k2WMcSw5Sz8ftUu33TEfA4",
+        );
+        registry
+    }
+
+    /// Issuers of a service on a network
+    pub fn get(&self, network: &XrplNetwork, service: &NamingService) -> &[Issuer] {
+        self.networks
+            .get(network.name())
+            .and_then(|services| services.get(service))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Add an issuer of a service on a network
+    pub fn add(&mut self, network: &XrplNetwork, service: NamingService, issuer: impl Into<Issuer>) {
+        self.networks
+            .entry(network.name().to_string())
+            .or_default()
+            .entry(service)
+            .or_default()
+            .push(issuer.into());
+    }
+
+    /// Replace the issuers of a service on a network
+    pub fn set(&mut self, network: &XrplNetwork, service: NamingService, issuers: Vec<Issuer>) {
+        self.networks
+            .entry(network.name().to_string())
+            .or_default()
+            .insert(service, issuers);
+    }
+
+    /// Take every service's issuers from `other` where it lists any
+    pub fn merge(&mut self, other: IssuerRegistry) {
+        for (network, services) in other.networks {
+            self.networks.entry(network).or_default().extend(services);
+        }
+    }
+
+    /// Check every issuer address
+    pub fn validate(&self) -> XnsResult<()> {
+        for (network, services) in &self.networks {
+            for (service, issuers) in services {
+                check_issuers(service, issuers).map_err(|e| {
+                    XnsError::ConfigError(format!("{} on {}", e, network))
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that issuer addresses are valid classic addresses
+pub(crate) fn check_issuers(service: &NamingService, issuers: &[Issuer]) -> XnsResult<()> {
+    for issuer in issuers {
+        decode_account_id(&issuer.address).map_err(|_| {
+            XnsError::ConfigError(format!("Invalid issuer address for {}: {}", service, issuer.address))
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: &str = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh";
+    const GENESIS_ID: &str = "B5F762798A53D543A014CAF8B297CFF8F2F937E8";
+
+    #[test]
+    fn test_issuer_taxon_filter() {
+        let nft = |taxon_and_seq: &str| XrplNft {
+            nft_token_id: format!("00080000{}{}", GENESIS_ID, taxon_and_seq),
+            uri: None,
+            issuer: None,
+            taxon: None,
+            owner: None,
+        };
+        // Taxon 0 and taxon 7, both with sequence 1
+        let taxon_0 = nft("16E5DA9C00000001");
+        let taxon_7 = nft("16E5DA9B00000001");
+
+        assert!(Issuer::new(GENESIS).matches(&taxon_0));
+        assert!(Issuer::new(GENESIS).with_taxon(7).matches(&taxon_7));
+        assert!(!Issuer::new(GENESIS).with_taxon(7).matches(&taxon_0));
+        assert!(!Issuer::new("rYhfynZDrde1uSvvQAYctApg6DnVE5HKm").matches(&taxon_7));
+    }
+
+    #[test]
+    fn test_registry_from_toml() {
+        let toml = r#"
+            [testnet]
+            xns = ["rYhfynZDrde1uSvvQAYctApg6DnVE5HKm"]
+
+            [devnet]
+            acme = [{ address = "rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh", taxon = 7 }]
+        "#;
+        let registry: IssuerRegistry = toml::from_str(toml).unwrap();
+        registry.validate().unwrap();
+
+        assert_eq!(registry.get(&XrplNetwork::Testnet, &NamingService::XNS).len(), 1);
+        let acme = NamingService::Custom("acme".to_string());
+        assert_eq!(
+            registry.get(&XrplNetwork::Devnet, &acme),
+            &[Issuer::new(GENESIS).with_taxon(7)]
+        );
+        assert!(registry.get(&XrplNetwork::Mainnet, &NamingService::XNS).is_empty());
+
+        let json = serde_json::to_string(&registry).unwrap();
+        assert_eq!(serde_json::from_str::<IssuerRegistry>(&json).unwrap(), registry);
+    }
+}
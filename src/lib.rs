@@ -20,16 +20,49 @@
 //! }
 //! ```
 
+mod address;
+pub mod cache;
 pub mod client;
+pub mod config;
+mod confusable;
+pub mod did_document;
+pub mod did_storage;
+pub mod domain;
 pub mod error;
+pub mod expiry;
+pub mod issuers;
 pub mod models;
 pub mod parser;
+pub mod provider;
+pub mod records;
 pub mod resolver;
+mod owner;
+mod singleflight;
 pub mod memo_storage;
+#[cfg(test)]
+mod mock_rpc;
+pub mod watch;
 
 // Re-exports
+pub use cache::{CacheStats, CachedValue, FileCache, MokaCache, NoopCache, ResolverCache};
 pub use client::{XrplClient, XrplNetwork};
+pub use config::{
+    CacheBackend, CacheConfig, ConfusableConfig, ConfusableMode, ExpiryConfig, ExpiryMode, MetadataPacing,
+    ResolutionPolicy, ResolverConfig, XnsResolverBuilder,
+};
+pub use did_document::{DidDocument, DidService, VerificationMethod};
+pub use did_storage::{DidEntry, DidRecords, DidStorage, DidTransaction, OwnerDid};
+pub use domain::DomainName;
 pub use error::{XnsError, XnsResult};
-pub use models::{DomainInfo, NamingService, NftMetadata};
+pub use issuers::{Issuer, IssuerRegistry};
+pub use models::{
+    BatchResolution, BatchStats, CustomNetwork, DidObject, DomainInfo, DomainWarning, ExpiryStatus, NamingService,
+    NftMetadata, OwnerStatus, Registration,
+};
+pub use provider::{IssuerProvider, NameServiceProvider, XrpDomainsProvider};
+pub use records::{
+    NameRecords, Record, RecordConflict, RecordKind, RecordPrecedence, RecordSource, RecordSourceKind,
+};
 pub use resolver::XnsResolver;
-pub use memo_storage::{MemoStorage, AddressStorageTransaction};
+pub use memo_storage::{MemoStorage, MemoEntry, OwnerMemo, AddressStorageTransaction, SubdomainRecord};
+pub use watch::{DomainEvent, DomainWatch, WatchMode, WatchOptions};
@@ -1,44 +1,64 @@
-///! Memo-based on-chain address storage for XNS domains
-///!
-///! This module provides functionality to store and retrieve blockchain addresses
-///! for .xrp domains using XRPL transaction memos. This is a decentralized storage
-///! solution that doesn't require external infrastructure.
-///!
-///! ## How It Works
-///!
-///! 1. User signs an XRPL transaction with a memo containing their address mappings
-///! 2. Transaction is sent to self (1 drop XRP payment)
-///! 3. Memo contains JSON: `{"BTC":"bc1q...", "ETH":"0x...", ...}`
-///! 4. SDK queries account transactions and finds latest XNS_ADDRESSES memo
-///!
-///! ## Example
-///!
-///! ```no_run
-///! use xns_sdk_rs::{XnsResolver, XrplNetwork};
-///! use std::collections::HashMap;
-///!
-///! #[tokio::main]
-///! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-///!     let resolver = XnsResolver::new(XrplNetwork::Mainnet).await?;
-///!
-///!     // Get addresses stored in memos
-///!     let addresses = resolver.get_memo_addresses("reRDmP8LxyYunhcfmQMnSjinKXV6duss6").await?;
-///!
-///!     println!("BTC: {:?}", addresses.get("BTC"));
-///!     println!("ETH: {:?}", addresses.get("ETH"));
-///!
-///!     Ok(())
-///! }
-///! ```
+//! Memo-based on-chain address storage for XNS domains
+//!
+//! This module provides functionality to store and retrieve blockchain addresses
+//! for .xrp domains using XRPL transaction memos. This is a decentralized storage
+//! solution that doesn't require external infrastructure.
+//!
+//! ## How It Works
+//!
+//! 1. User signs an XRPL transaction with a memo containing their address mappings
+//! 2. Transaction is sent to self (1 drop XRP payment)
+//! 3. Memo contains JSON: `{"BTC":"bc1q...", "ETH":"0x...", ...}`
+//! 4. SDK queries account transactions and finds latest XNS_ADDRESSES memo
+//!
+//! Subdomains (`pay.ckelley.xrp`) are stored the same way: the owner of
+//! `ckelley.xrp` sends itself an `XNS_SUBDOMAINS` memo mapping subdomain
+//! labels to [`SubdomainRecord`]s. Only memos on transactions sent by the
+//! account itself are read, so nobody else can inject records.
+//!
+//! An account picks its primary name, the one shown for its address, with an
+//! `XNS_PRIMARY_NAME` memo holding the name. Anyone can claim any name this
+//! way, so it is only trusted once the name resolves back to the account.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use xns_sdk_rs::{XnsResolver, XrplNetwork};
+//! use std::collections::HashMap;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let resolver = XnsResolver::new(XrplNetwork::Mainnet).await?;
+//!
+//!     // Get addresses stored in memos
+//!     let addresses = resolver.get_memo_addresses("reRDmP8LxyYunhcfmQMnSjinKXV6duss6").await?;
+//!
+//!     println!("BTC: {:?}", addresses.get("BTC"));
+//!     println!("ETH: {:?}", addresses.get("ETH"));
+//!
+//!     Ok(())
+//! }
+//! ```
 
 use crate::error::{XnsError, XnsResult};
 use crate::client::XrplClient;
+use crate::owner;
+use crate::models::{AccountTransaction, XrplNetwork};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 
 /// Memo type identifier for XNS address records
 pub const XNS_ADDRESSES_MEMO_TYPE: &str = "XNS_ADDRESSES";
 
+/// Memo type identifier for XNS subdomain records
+pub const XNS_SUBDOMAINS_MEMO_TYPE: &str = "XNS_SUBDOMAINS";
+
+/// Memo type identifier for an account's primary name
+pub const XNS_PRIMARY_NAME_MEMO_TYPE: &str = "XNS_PRIMARY_NAME";
+
+const MEMO_PAGE_SIZE: u32 = 200;
+
 /// Address record stored in XRPL memo
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddressRecord {
@@ -53,6 +73,45 @@ pub struct AddressRecord {
     pub label: Option<String>,
 }
 
+/// Records for one subdomain, keyed by its label(s) below the parent name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubdomainRecord {
+    /// XRPL address the subdomain points to (defaults to the parent's owner)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    /// Addresses on other chains (e.g. BTC, ETH)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub addresses: HashMap<String, String>,
+
+    /// Text records
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub text_records: HashMap<String, String>,
+}
+
+/// Newest memo of a type sent by an account, with the ledger it was sent in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoEntry {
+    /// Decoded memo data
+    pub data: String,
+
+    pub ledger_index: Option<u32>,
+
+    /// Close time of the memo's ledger, as Unix time
+    pub timestamp: Option<u64>,
+}
+
+/// An NFT owner's newest memo of a type written since acquiring the NFT
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnerMemo {
+    /// The memo, unless the owner has written none since acquiring the NFT
+    pub memo: Option<MemoEntry>,
+
+    /// Ledger in which the owner acquired the NFT, if it was within the
+    /// scanned history
+    pub acquired_ledger: Option<u32>,
+}
+
 /// Transaction memo structure
 #[derive(Debug, Serialize)]
 pub struct TransactionMemo {
@@ -86,6 +145,10 @@ pub struct AddressStorageTransaction {
 
     #[serde(rename = "Memos")]
     pub memos: Vec<TransactionMemo>,
+
+    /// Required on chains whose NetworkID is above 1024
+    #[serde(rename = "NetworkID", skip_serializing_if = "Option::is_none")]
+    pub network_id: Option<u32>,
 }
 
 impl AddressStorageTransaction {
@@ -95,11 +158,30 @@ impl AddressStorageTransaction {
         let addresses_json = serde_json::to_string(&addresses)
             .map_err(|e| XnsError::InvalidInput(format!("Failed to serialize addresses: {}", e)))?;
 
+        Ok(Self::with_memo(account, XNS_ADDRESSES_MEMO_TYPE, &addresses_json))
+    }
+
+    /// Create a new transaction for storing subdomain records
+    ///
+    /// The memo replaces every previously stored subdomain of the account.
+    pub fn subdomains(account: String, records: HashMap<String, SubdomainRecord>) -> XnsResult<Self> {
+        let records_json = serde_json::to_string(&records)
+            .map_err(|e| XnsError::InvalidInput(format!("Failed to serialize subdomains: {}", e)))?;
+
+        Ok(Self::with_memo(account, XNS_SUBDOMAINS_MEMO_TYPE, &records_json))
+    }
+
+    /// Create a new transaction setting the account's primary name
+    pub fn primary_name(account: String, name: &str) -> Self {
+        Self::with_memo(account, XNS_PRIMARY_NAME_MEMO_TYPE, name)
+    }
+
+    fn with_memo(account: String, memo_type: &str, memo_data: &str) -> Self {
         // Hex-encode the JSON (XRPL requirement)
-        let memo_data_hex = hex::encode(addresses_json.as_bytes());
-        let memo_type_hex = hex::encode(XNS_ADDRESSES_MEMO_TYPE.as_bytes());
+        let memo_data_hex = hex::encode(memo_data.as_bytes());
+        let memo_type_hex = hex::encode(memo_type.as_bytes());
 
-        Ok(Self {
+        Self {
             transaction_type: "Payment".to_string(),
             account: account.clone(),
             destination: account, // Self-payment
@@ -110,7 +192,14 @@ impl AddressStorageTransaction {
                     memo_data: memo_data_hex,
                 },
             }],
-        })
+            network_id: None,
+        }
+    }
+
+    /// Set the `NetworkID` the transaction is bound to
+    pub fn for_network(mut self, network: &XrplNetwork) -> Self {
+        self.network_id = network.transaction_network_id();
+        self
     }
 }
 
@@ -133,26 +222,183 @@ impl MemoStorage {
         account: &str,
         addresses: HashMap<String, String>,
     ) -> XnsResult<String> {
-        let tx = AddressStorageTransaction::new(account.to_string(), addresses)?;
+        let tx = AddressStorageTransaction::new(account.to_string(), addresses)?
+            .for_network(&self.client.network());
         let tx_json = serde_json::to_string_pretty(&tx)
             .map_err(|e| XnsError::InvalidInput(format!("Failed to serialize transaction: {}", e)))?;
 
         Ok(tx_json)
     }
 
+    /// Build an unsigned transaction for storing subdomain records
+    pub fn build_subdomain_transaction(
+        &self,
+        account: &str,
+        records: HashMap<String, SubdomainRecord>,
+    ) -> XnsResult<String> {
+        let tx = AddressStorageTransaction::subdomains(account.to_string(), records)?
+            .for_network(&self.client.network());
+        serde_json::to_string_pretty(&tx)
+            .map_err(|e| XnsError::InvalidInput(format!("Failed to serialize transaction: {}", e)))
+    }
+
+    /// Build an unsigned transaction setting the account's primary name
+    pub fn build_primary_name_transaction(&self, account: &str, name: &str) -> XnsResult<String> {
+        let tx = AddressStorageTransaction::primary_name(account.to_string(), name)
+            .for_network(&self.client.network());
+        serde_json::to_string_pretty(&tx)
+            .map_err(|e| XnsError::InvalidInput(format!("Failed to serialize transaction: {}", e)))
+    }
+
     /// Query account transactions to find latest XNS_ADDRESSES memo
     pub async fn get_addresses(&self, account: &str) -> XnsResult<HashMap<String, String>> {
-        // Query account transactions
-        let tx_response = self.client.account_info(account).await?;
+        match self.latest_memo(account, XNS_ADDRESSES_MEMO_TYPE).await? {
+            Some(memo_data) => Self::parse_addresses(&memo_data),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// Query account transactions to find latest XNS_SUBDOMAINS memo
+    pub async fn get_subdomains(&self, account: &str) -> XnsResult<HashMap<String, SubdomainRecord>> {
+        match self.latest_memo(account, XNS_SUBDOMAINS_MEMO_TYPE).await? {
+            Some(memo_data) => Self::parse_subdomains(&memo_data),
+            None => Ok(HashMap::new()),
+        }
+    }
 
-        // For now, return empty - full implementation would:
-        // 1. Use account_tx RPC method to get transactions
-        // 2. Parse transaction memos
-        // 3. Find latest XNS_ADDRESSES memo
-        // 4. Decode hex and parse JSON
+    /// Primary name claimed in the account's latest XNS_PRIMARY_NAME memo
+    ///
+    /// The claim is not verified; see `XnsResolver::primary_name`.
+    pub async fn get_primary_name(&self, account: &str) -> XnsResult<Option<String>> {
+        Ok(self
+            .latest_memo(account, XNS_PRIMARY_NAME_MEMO_TYPE)
+            .await?
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty()))
+    }
 
-        tracing::warn!("Memo address retrieval not yet fully implemented");
-        Ok(HashMap::new())
+    /// Decoded data of the newest memo of `memo_type` sent by `account`
+    pub async fn latest_memo(&self, account: &str, memo_type: &str) -> XnsResult<Option<String>> {
+        Ok(self.latest_memo_entry(account, memo_type).await?.map(|entry| entry.data))
+    }
+
+    /// Newest memo of `memo_type` sent by `account`, with its ledger
+    pub async fn latest_memo_entry(&self, account: &str, memo_type: &str) -> XnsResult<Option<MemoEntry>> {
+        let mut marker = None;
+
+        for _ in 0..self.client.history_pages() {
+            let page = self.client.account_tx(account, marker, MEMO_PAGE_SIZE).await?;
+
+            if let Some((entry, memo_data)) = page
+                .transactions
+                .iter()
+                .find_map(|entry| Self::memo_in(entry, account, memo_type).map(|memo| (entry, memo)))
+            {
+                return Ok(Some(MemoEntry {
+                    data: Self::decode_memo(&memo_data)?,
+                    ledger_index: entry.ledger_index(),
+                    timestamp: entry.timestamp(),
+                }));
+            }
+
+            marker = page.marker;
+            if marker.is_none() {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Newest memo of `memo_type` written by `owner` since it acquired `nft_id`
+    ///
+    /// Memos are keyed by account rather than by name, so records the owner
+    /// wrote before acquiring the NFT, for another name or before selling
+    /// and buying it back, must not apply to it. History is scanned back to the
+    /// mint or transfer that brought the NFT to `owner`; when that is older
+    /// than the scanned pages, every memo found is newer than it.
+    pub async fn owner_memo(&self, owner: &str, memo_type: &str, nft_id: &str) -> XnsResult<OwnerMemo> {
+        let prefix = self.client.network().address_prefix();
+        let mut found = OwnerMemo::default();
+        let mut marker = None;
+
+        for _ in 0..self.client.history_pages() {
+            let page = self.client.account_tx(owner, marker, MEMO_PAGE_SIZE).await?;
+            if Self::scan_owner_memos(&mut found, &page.transactions, owner, memo_type, nft_id, prefix) {
+                return Ok(found);
+            }
+
+            marker = page.marker;
+            if marker.is_none() {
+                return Ok(found);
+            }
+        }
+
+        owner::history_exhausted(&self.client, owner, nft_id);
+        Ok(found)
+    }
+
+    /// Scan a page of history, newest first, for [`Self::owner_memo`]
+    ///
+    /// Returns true once the transaction that brought `nft_id` to `owner`
+    /// is reached. Memos that do not decode, or whose data is malformed for
+    /// their type, are skipped so one bad write does not hide the owner's
+    /// older records.
+    fn scan_owner_memos(
+        found: &mut OwnerMemo,
+        transactions: &[AccountTransaction],
+        owner: &str,
+        memo_type: &str,
+        nft_id: &str,
+        prefix: u8,
+    ) -> bool {
+        for entry in transactions {
+            if found.memo.is_none()
+                && let Some(memo_data) = Self::memo_in(entry, owner, memo_type)
+            {
+                match Self::decode_memo(&memo_data).and_then(|data| Self::check_memo(memo_type, data)) {
+                    Ok(data) => {
+                        found.memo = Some(MemoEntry {
+                            data,
+                            ledger_index: entry.ledger_index(),
+                            timestamp: entry.timestamp(),
+                        });
+                    }
+                    Err(e) => tracing::debug!("Skipping {} memo of {}: {}", memo_type, owner, e),
+                }
+            }
+
+            if owner::transfer_in(entry, owner, nft_id, prefix) {
+                // A memo in the acquiring transaction itself counts
+                found.acquired_ledger = entry.ledger_index();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Hex memo data of `memo_type` on a successful transaction sent by `account`
+    fn memo_in(entry: &AccountTransaction, account: &str, memo_type: &str) -> Option<String> {
+        if entry.tx.get("Account").and_then(Value::as_str) != Some(account)
+            || entry.meta.get("TransactionResult").and_then(Value::as_str) != Some("tesSUCCESS")
+        {
+            return None;
+        }
+
+        let memo_type_hex = hex::encode(memo_type.as_bytes());
+        entry
+            .tx
+            .get("Memos")?
+            .as_array()?
+            .iter()
+            .filter_map(|memo| memo.get("Memo"))
+            .find(|memo| {
+                memo.get("MemoType")
+                    .and_then(Value::as_str)
+                    .is_some_and(|t| t.eq_ignore_ascii_case(&memo_type_hex))
+            })
+            .and_then(|memo| memo.get("MemoData").and_then(Value::as_str))
+            .map(str::to_string)
     }
 
     /// Decode a hex-encoded memo
@@ -164,16 +410,32 @@ impl MemoStorage {
             .map_err(|e| XnsError::InvalidInput(format!("Invalid UTF-8 in memo: {}", e)))
     }
 
+    /// Check that decoded memo data parses as its type's records
+    fn check_memo(memo_type: &str, data: String) -> XnsResult<String> {
+        match memo_type {
+            XNS_ADDRESSES_MEMO_TYPE => Self::parse_addresses(&data).map(|_| data),
+            XNS_SUBDOMAINS_MEMO_TYPE => Self::parse_subdomains(&data).map(|_| data),
+            _ => Ok(data),
+        }
+    }
+
     /// Parse address data from decoded memo
     pub fn parse_addresses(memo_data: &str) -> XnsResult<HashMap<String, String>> {
         serde_json::from_str(memo_data)
             .map_err(|e| XnsError::InvalidInput(format!("Invalid address JSON: {}", e)))
     }
+
+    /// Parse subdomain records from decoded memo
+    pub fn parse_subdomains(memo_data: &str) -> XnsResult<HashMap<String, SubdomainRecord>> {
+        serde_json::from_str(memo_data)
+            .map_err(|e| XnsError::InvalidInput(format!("Invalid subdomain JSON: {}", e)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::CustomNetwork;
 
     #[test]
     fn test_build_transaction() {
@@ -188,6 +450,13 @@ mod tests {
         assert_eq!(tx.transaction_type, "Payment");
         assert_eq!(tx.amount, "1");
         assert_eq!(tx.account, tx.destination);
+        assert!(!serde_json::to_string(&tx).unwrap().contains("NetworkID"));
+
+        let sidechain = XrplNetwork::Custom(
+            CustomNetwork::new("sidechain", "http://localhost:5005").with_network_id(21337),
+        );
+        let tx = tx.for_network(&sidechain);
+        assert_eq!(serde_json::to_value(&tx).unwrap()["NetworkID"], 21337);
     }
 
     #[test]
@@ -199,6 +468,117 @@ mod tests {
         assert_eq!(decoded, data);
     }
 
+    #[test]
+    fn test_memo_in_own_transactions_only() {
+        let mut records = HashMap::new();
+        records.insert("pay".to_string(), SubdomainRecord {
+            address: Some("rPay".to_string()),
+            ..Default::default()
+        });
+        let tx = AddressStorageTransaction::subdomains("rAlice".to_string(), records).unwrap();
+
+        let entry = |sender: &str| AccountTransaction {
+            tx: serde_json::json!({ "Account": sender, "Memos": tx.memos }),
+            meta: serde_json::json!({ "TransactionResult": "tesSUCCESS" }),
+            ledger_index: None,
+        };
+
+        let memo = MemoStorage::memo_in(&entry("rAlice"), "rAlice", XNS_SUBDOMAINS_MEMO_TYPE).unwrap();
+        let decoded: HashMap<String, SubdomainRecord> =
+            serde_json::from_str(&MemoStorage::decode_memo(&memo).unwrap()).unwrap();
+        assert_eq!(decoded["pay"].address.as_deref(), Some("rPay"));
+
+        assert!(MemoStorage::memo_in(&entry("rMallory"), "rAlice", XNS_SUBDOMAINS_MEMO_TYPE).is_none());
+        assert!(MemoStorage::memo_in(&entry("rAlice"), "rAlice", XNS_ADDRESSES_MEMO_TYPE).is_none());
+
+        let primary = AddressStorageTransaction::primary_name("rAlice".to_string(), "ckelley.xrp");
+        let entry = AccountTransaction {
+            tx: serde_json::json!({ "Account": "rAlice", "Memos": primary.memos }),
+            ..entry("rAlice")
+        };
+        let memo = MemoStorage::memo_in(&entry, "rAlice", XNS_PRIMARY_NAME_MEMO_TYPE).unwrap();
+        assert_eq!(MemoStorage::decode_memo(&memo).unwrap(), "ckelley.xrp");
+    }
+
+    #[test]
+    fn test_owner_memo_stops_at_acquisition() {
+        use crate::address::{ACCOUNT_ID_PREFIX, encode_account_id};
+
+        const NFT: &str = "00080000B5F762798A53D543A014CAF8B297CFF8F2F937E80000099B00000000";
+        let owner_id = [0x22; 20];
+        let owner = encode_account_id(&owner_id);
+        let owner_page = format!("{}{}", hex::encode_upper(owner_id), "F".repeat(24));
+
+        let memo_of = |memo_type: &str, ledger: u32, memo_data: &str| AccountTransaction {
+            tx: serde_json::json!({
+                "Account": owner,
+                "Memos": [{ "Memo": {
+                    "MemoType": hex::encode(memo_type),
+                    "MemoData": memo_data
+                }}]
+            }),
+            meta: serde_json::json!({ "TransactionResult": "tesSUCCESS" }),
+            ledger_index: Some(ledger),
+        };
+        let memo = |ledger: u32, memo_data: &str| memo_of(XNS_PRIMARY_NAME_MEMO_TYPE, ledger, memo_data);
+        let acquired = AccountTransaction {
+            tx: serde_json::json!({ "Account": owner, "TransactionType": "NFTokenAcceptOffer" }),
+            meta: serde_json::json!({
+                "TransactionResult": "tesSUCCESS",
+                "AffectedNodes": [{ "CreatedNode": {
+                    "LedgerEntryType": "NFTokenPage",
+                    "LedgerIndex": owner_page,
+                    "NewFields": { "NFTokens": [{ "NFToken": { "NFTokenID": NFT } }] }
+                }}]
+            }),
+            ledger_index: Some(20),
+        };
+        let scan_type = |memo_type: &str, transactions: &[AccountTransaction]| {
+            let mut found = OwnerMemo::default();
+            let done = MemoStorage::scan_owner_memos(
+                &mut found,
+                transactions,
+                &owner,
+                memo_type,
+                NFT,
+                ACCOUNT_ID_PREFIX,
+            );
+            (done, found)
+        };
+        let scan = |transactions: &[AccountTransaction]| scan_type(XNS_PRIMARY_NAME_MEMO_TYPE, transactions);
+
+        // Newest first: an undecodable memo, a memo after acquiring, then
+        // one written before
+        let (done, found) = scan(&[
+            memo(40, "ZZ"),
+            memo(30, &hex::encode("after.xrp")),
+            acquired.clone(),
+            memo(10, &hex::encode("before.xrp")),
+        ]);
+        assert!(done);
+        assert_eq!(found.acquired_ledger, Some(20));
+        let entry = found.memo.unwrap();
+        assert_eq!(entry.data, "after.xrp");
+        assert_eq!(entry.ledger_index, Some(30));
+
+        let (done, found) = scan(&[acquired, memo(10, &hex::encode("before.xrp"))]);
+        assert!(done);
+        assert_eq!(found.acquired_ledger, Some(20));
+        assert!(found.memo.is_none());
+
+        // Acquisition not reached: the page's memos are all newer than it
+        let (done, found) = scan(&[memo(30, &hex::encode("after.xrp"))]);
+        assert!(!done);
+        assert_eq!(found.memo.unwrap().data, "after.xrp");
+
+        // Valid hex holding malformed JSON is skipped too
+        let addresses = |ledger: u32, data: &str| memo_of(XNS_ADDRESSES_MEMO_TYPE, ledger, &hex::encode(data));
+        let (_, found) = scan_type(XNS_ADDRESSES_MEMO_TYPE, &[addresses(40, "{\"BTC\":"), addresses(30, r#"{"BTC":"bc1"}"#)]);
+        let entry = found.memo.unwrap();
+        assert_eq!(entry.data, r#"{"BTC":"bc1"}"#);
+        assert_eq!(entry.ledger_index, Some(30));
+    }
+
     #[test]
     fn test_parse_addresses() {
         let json = r#"{"BTC":"bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh","ETH":"0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb"}"#;
@@ -0,0 +1,234 @@
+//! JSON-RPC server answering rippled and Clio methods in tests
+//!
+//! Each request is answered by a handler given the method name and its
+//! first params object; the returned value becomes the response `result`.
+//! A custom network pointing at the server without a Clio URL sends both
+//! rippled and Clio calls to it. [`xns_ledger`] is a handler serving XNS
+//! names, and [`MockMetadata`] serves their metadata over HTTP.
+
+use crate::address::{decode_account_id, encode_account_id};
+use crate::config::XnsResolverBuilder;
+use crate::memo_storage::AddressStorageTransaction;
+use crate::models::{CustomNetwork, NamingService, XrplNetwork};
+use crate::resolver::XnsResolver;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+type Handler = dyn Fn(&str, &Value) -> Value + Send + Sync;
+
+/// Issuer of the names served by [`xns_ledger`]
+pub(crate) const ISSUER: &str = "rYhfynZDrde1uSvvQAYctApg6DnVE5HKm";
+
+pub(crate) struct MockRpc {
+    url: String,
+}
+
+impl MockRpc {
+    pub(crate) async fn start(handler: impl Fn(&str, &Value) -> Value + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let _ = serve(stream, handler.as_ref()).await;
+                });
+            }
+        });
+
+        Self { url }
+    }
+
+    /// A network whose rippled and Clio endpoints are this server
+    pub(crate) fn network(&self) -> XrplNetwork {
+        XrplNetwork::Custom(self.custom_network())
+    }
+
+    pub(crate) fn custom_network(&self) -> CustomNetwork {
+        CustomNetwork::new("mock", self.url.clone()).with_network_id(1)
+    }
+
+    /// A resolver for the XNS names of [`xns_ledger`] on this server
+    pub(crate) fn resolver(&self) -> XnsResolverBuilder {
+        XnsResolver::builder()
+            .network(self.network())
+            .services([NamingService::XNS])
+            .issuer(NamingService::XNS, ISSUER)
+            .metadata_pacing(None)
+    }
+}
+
+pub(crate) fn account(byte: u8) -> String {
+    encode_account_id(&[byte; 20])
+}
+
+/// A ledger holding XNS names minted by [`ISSUER`], as `(name, owner)`,
+/// and primary name memos, as `(account, name)`
+pub(crate) fn xns_ledger(
+    names: &[(&str, &str)],
+    claims: &[(&str, &str)],
+) -> impl Fn(&str, &Value) -> Value + Send + Sync + 'static {
+    ledger(names, claims, |name| json!({ "name": name }).to_string())
+}
+
+/// Like [`xns_ledger`], with each name's metadata hosted on `metadata`
+pub(crate) fn hosted_xns_ledger(
+    names: &[(&str, &str)],
+    metadata: &MockMetadata,
+) -> impl Fn(&str, &Value) -> Value + Send + Sync + 'static {
+    ledger(names, &[], |name| format!("{}/{}", metadata.url, name))
+}
+
+fn ledger(
+    names: &[(&str, &str)],
+    claims: &[(&str, &str)],
+    uri: impl Fn(&str) -> String,
+) -> impl Fn(&str, &Value) -> Value + Send + Sync + 'static {
+    let issuer_id = hex::encode_upper(decode_account_id(ISSUER).unwrap());
+    let nfts: Vec<(String, String, String)> = names
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, owner))| (format!("00080000{}{:016X}", issuer_id, idx), uri(name), owner.to_string()))
+        .collect();
+    let claims: Vec<(String, Value)> = claims
+        .iter()
+        .map(|(account, name)| {
+            let tx = AddressStorageTransaction::primary_name(account.to_string(), name);
+            (account.to_string(), json!(tx.memos))
+        })
+        .collect();
+
+    move |method, params| match method {
+        "nfts_by_issuer" => json!({
+            "nfts": nfts.iter().map(|(id, uri, owner)| json!({
+                "nft_id": id,
+                "owner": owner,
+                "issuer": ISSUER,
+                "uri": hex::encode(uri),
+            })).collect::<Vec<_>>()
+        }),
+        "nft_info" => {
+            let (id, _, owner) = nfts.iter().find(|(id, ..)| params["nft_id"] == *id).unwrap();
+            json!({ "nft_id": id, "owner": owner, "is_burned": false })
+        }
+        "account_tx" => json!({
+            "transactions": claims.iter().filter(|(account, _)| params["account"] == *account).map(|(account, memos)| json!({
+                "tx": { "Account": account, "Memos": memos },
+                "meta": { "TransactionResult": "tesSUCCESS" },
+            })).collect::<Vec<_>>()
+        }),
+        "ledger_entry" => json!({ "error": "entryNotFound" }),
+        _ => panic!("unexpected {} request", method),
+    }
+}
+
+/// HTTP server answering `GET /<name>` with `{"name": "<name>"}` after a
+/// delay, counting the requests it receives
+pub(crate) struct MockMetadata {
+    url: String,
+    requests: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+impl MockMetadata {
+    pub(crate) async fn start(delay: Duration) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let active = Arc::new(AtomicUsize::new(0));
+
+        let (counted, peaked) = (requests.clone(), peak.clone());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let (requests, peak, active) = (counted.clone(), peaked.clone(), active.clone());
+                tokio::spawn(async move {
+                    let Ok((buf, _)) = read_request(&mut stream).await else {
+                        return;
+                    };
+                    requests.fetch_add(1, Ordering::SeqCst);
+                    peak.fetch_max(active.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    tokio::time::sleep(delay).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+
+                    let request = String::from_utf8_lossy(&buf);
+                    let name = request.split_whitespace().nth(1).unwrap_or("/").trim_start_matches('/');
+                    let _ = respond(&mut stream, &json!({ "name": name })).await;
+                });
+            }
+        });
+
+        Self { url, requests, peak }
+    }
+
+    /// Requests received so far
+    pub(crate) fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// Most requests in progress at once
+    pub(crate) fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+async fn serve(mut stream: TcpStream, handler: &Handler) -> std::io::Result<()> {
+    let (buf, header_end) = read_request(&mut stream).await?;
+    if buf.is_empty() {
+        return Ok(());
+    }
+
+    let request: Value = serde_json::from_slice(&buf[header_end..]).unwrap_or(Value::Null);
+    let method = request["method"].as_str().unwrap_or_default();
+    let result = handler(method, &request["params"][0]);
+
+    respond(&mut stream, &json!({ "result": result })).await
+}
+
+/// Read a request, returning its bytes and where its body starts
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<(Vec<u8>, usize)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let (header_end, content_length) = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok((Vec::new(), 0));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let headers = String::from_utf8_lossy(&buf[..end]).to_ascii_lowercase();
+            let length = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            break (end + 4, length);
+        }
+    };
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    Ok((buf, header_end))
+}
+
+async fn respond(stream: &mut TcpStream, body: &Value) -> std::io::Result<()> {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -1,84 +1,469 @@
+use crate::error::XnsResult;
+use crate::records::NameRecords;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// XRPL network type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Public networks are named (`"mainnet"`); a custom network is configured
+/// as a table, e.g. `network = { custom = { name = "local", rpc_url =
+/// "http://localhost:5005" } }` in TOML.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum XrplNetwork {
     Mainnet,
     Testnet,
     Devnet,
+    /// A standalone rippled, private network or sidechain
+    Custom(CustomNetwork),
 }
 
 impl XrplNetwork {
-    pub fn rpc_url(&self) -> &'static str {
+    pub fn rpc_url(&self) -> &str {
         match self {
             XrplNetwork::Mainnet => "https://s1.ripple.com:51234",
             XrplNetwork::Testnet => "https://s.altnet.rippletest.net:51234",
             XrplNetwork::Devnet => "https://s.devnet.rippletest.net:51234",
+            XrplNetwork::Custom(network) => &network.rpc_url,
+        }
+    }
+
+    /// WebSocket URL, if the network has one
+    pub fn ws_url(&self) -> Option<&str> {
+        match self {
+            XrplNetwork::Mainnet => Some("wss://s1.ripple.com"),
+            XrplNetwork::Testnet => Some("wss://s.altnet.rippletest.net:51233"),
+            XrplNetwork::Devnet => Some("wss://s.devnet.rippletest.net:51233"),
+            XrplNetwork::Custom(network) => network.ws_url.as_deref(),
+        }
+    }
+
+    /// Network name, used in logs and as the network's key in issuer
+    /// registries
+    pub fn name(&self) -> &str {
+        match self {
+            XrplNetwork::Mainnet => "mainnet",
+            XrplNetwork::Testnet => "testnet",
+            XrplNetwork::Devnet => "devnet",
+            XrplNetwork::Custom(network) => &network.name,
+        }
+    }
+
+    /// NetworkID of the chain, if known
+    pub fn network_id(&self) -> Option<u32> {
+        match self {
+            XrplNetwork::Mainnet => Some(0),
+            XrplNetwork::Testnet => Some(1),
+            XrplNetwork::Devnet => Some(2),
+            XrplNetwork::Custom(network) => network.network_id,
+        }
+    }
+
+    /// `NetworkID` to put in transactions: required for chains above 1024,
+    /// and must be omitted below
+    pub fn transaction_network_id(&self) -> Option<u32> {
+        self.network_id().filter(|id| *id > 1024)
+    }
+
+    /// Clio server for Clio-only methods: the public Clio server, or a
+    /// custom network's Clio URL, falling back to its RPC URL
+    pub fn clio_url(&self) -> &str {
+        match self {
+            XrplNetwork::Custom(network) => network.clio_url.as_deref().unwrap_or(&network.rpc_url),
+            _ => crate::client::DEFAULT_CLIO_URL,
+        }
+    }
+
+    /// Type prefix byte of classic addresses
+    pub fn address_prefix(&self) -> u8 {
+        match self {
+            XrplNetwork::Custom(network) => network.address_prefix,
+            _ => crate::address::ACCOUNT_ID_PREFIX,
         }
     }
 }
 
+impl fmt::Display for XrplNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Endpoints and chain settings of a custom network
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CustomNetwork {
+    /// Network name, unique among configured networks
+    pub name: String,
+
+    /// rippled JSON-RPC URL
+    pub rpc_url: String,
+
+    /// rippled WebSocket URL, needed to watch domains by ledger subscription
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ws_url: Option<String>,
+
+    /// Clio URL; without one, Clio methods are sent to `rpc_url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clio_url: Option<String>,
+
+    /// NetworkID of the chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_id: Option<u32>,
+
+    /// Type prefix byte of classic addresses (0 on XRPL chains)
+    #[serde(default)]
+    pub address_prefix: u8,
+}
+
+impl CustomNetwork {
+    pub fn new(name: impl Into<String>, rpc_url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            rpc_url: rpc_url.into(),
+            ws_url: None,
+            clio_url: None,
+            network_id: None,
+            address_prefix: 0,
+        }
+    }
+
+    pub fn with_ws_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = Some(ws_url.into());
+        self
+    }
+
+    pub fn with_clio_url(mut self, clio_url: impl Into<String>) -> Self {
+        self.clio_url = Some(clio_url.into());
+        self
+    }
+
+    pub fn with_network_id(mut self, network_id: u32) -> Self {
+        self.network_id = Some(network_id);
+        self
+    }
+
+    pub fn with_address_prefix(mut self, prefix: u8) -> Self {
+        self.address_prefix = prefix;
+        self
+    }
+}
+
 /// Naming service type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+///
+/// Serialized as its lowercase name (`"xns"`, `"xrpdomains"`); any other name
+/// is a [`NamingService::Custom`] service.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum NamingService {
     /// XRP Name Service (xrpns.com)
     XNS,
     /// XRP Domains (xrpdomains.xyz)
     XRPDomains,
+    /// Another service, served by a registered provider or configured issuers
+    Custom(String),
 }
 
 impl NamingService {
-    /// Get the known issuer address for this naming service
-    pub fn issuer_address(&self, network: XrplNetwork) -> Option<&'static str> {
-        match (self, network) {
-            // XNS (xrpns.com) - Verified from ckelley.xrp NFT
-            (NamingService::XNS, XrplNetwork::Mainnet) => {
-                Some("rYhfynZDrde1uSvvQAYctApg6DnVE5HKm")
-            }
-            (NamingService::XNS, XrplNetwork::Testnet) => {
-                // TODO: Find testnet issuer address
-                None
-            }
-            // XRP Domains (xrpdomains.xyz) - From xrp.cafe research
-            (NamingService::XRPDomains, XrplNetwork::Mainnet) => {
-                Some("r4pM3nT7r7X1k2WMcSw5Sz8ftUu33TEfA4")
-            }
-            _ => None,
+    /// Lowercase name used in configs and serialized results
+    pub fn name(&self) -> &str {
+        match self {
+            NamingService::XNS => "xns",
+            NamingService::XRPDomains => "xrpdomains",
+            NamingService::Custom(name) => name,
         }
     }
 }
 
+impl fmt::Display for NamingService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl From<&str> for NamingService {
+    fn from(name: &str) -> Self {
+        match name {
+            "xns" => NamingService::XNS,
+            "xrpdomains" => NamingService::XRPDomains,
+            name => NamingService::Custom(name.to_string()),
+        }
+    }
+}
+
+impl Serialize for NamingService {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for NamingService {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| NamingService::from(name.as_str()))
+    }
+}
+
 /// Domain information resolved from XRPL
+///
+/// Fields are added as the resolver learns more about names, so outside this
+/// crate a `DomainInfo` is built with [`DomainInfo::new`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct DomainInfo {
     /// Domain name (e.g., "ckelley.xrp")
     pub domain: String,
 
-    /// Owner XRPL address
+    /// Owner XRPL address (empty when `owner_status` is `Unknown`)
     pub owner: String,
 
+    /// How the owner was determined
+    #[serde(default)]
+    pub owner_status: OwnerStatus,
+
     /// NFT token ID
     pub nft_id: String,
 
     /// Naming service provider
     pub service: NamingService,
 
-    /// Additional addresses (e.g., BTC, ETH)
+    /// Additional addresses (e.g., BTC, ETH), the preferred value of each
+    /// coin in `records`
     #[serde(default)]
     pub addresses: HashMap<String, String>,
 
-    /// Text records
+    /// Text records, the preferred value of each key in `records`
     #[serde(default)]
     pub text_records: HashMap<String, String>,
 
+    /// Every value found for each record, with its source, best first
+    #[serde(default, skip_serializing_if = "NameRecords::is_empty")]
+    pub records: NameRecords,
+
+    /// Ledger in which the owner acquired the name NFT, found while reading
+    /// the owner's memo or DID records. Records written before it are
+    /// ignored. Only set when memo or DID records are enabled (see
+    /// [`XnsResolverBuilder::memo_records`](crate::XnsResolverBuilder::memo_records))
+    /// and the acquisition is within the scanned account history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquired_ledger: Option<u32>,
+
     /// Expiration timestamp (if any)
     pub expires_at: Option<u64>,
 
+    /// Whether the name has expired, as of when it was returned
+    #[serde(default)]
+    pub expiry: ExpiryStatus,
+
     /// Raw metadata
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<NftMetadata>,
+
+    /// Hex-encoded URI the metadata was read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_uri: Option<String>,
+
+    /// Unix timestamp when this information was read from the ledger
+    #[serde(default)]
+    pub resolved_at: Option<u64>,
+
+    /// Age of the data when a stale cached result was returned while a
+    /// background refresh runs (stale-while-revalidate mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_age: Option<Duration>,
+
+    /// Reasons the name may be impersonating another one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DomainWarning>,
+
+    /// Registered name a subdomain belongs to (`ckelley.xrp` for
+    /// `pay.ckelley.xrp`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// The same name registered with other naming services, when the
+    /// resolver is configured with `ResolutionPolicy::ReturnAll`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_registrations: Vec<Registration>,
+}
+
+impl DomainInfo {
+    /// A name resolved now to `owner`, without records, expiry or metadata
+    pub fn new(
+        domain: impl Into<String>,
+        owner: impl Into<String>,
+        owner_status: OwnerStatus,
+        nft_id: impl Into<String>,
+        service: NamingService,
+    ) -> Self {
+        Self {
+            domain: domain.into(),
+            owner: owner.into(),
+            owner_status,
+            nft_id: nft_id.into(),
+            service,
+            addresses: HashMap::new(),
+            text_records: HashMap::new(),
+            records: NameRecords::default(),
+            acquired_ledger: None,
+            expires_at: None,
+            expiry: ExpiryStatus::Active,
+            metadata: None,
+            metadata_uri: None,
+            resolved_at: Some(unix_now()),
+            stale_age: None,
+            warnings: Vec::new(),
+            parent: None,
+            other_registrations: Vec::new(),
+        }
+    }
+
+    /// An XNS name owned by `owner`, as seen on the ledger
+    #[cfg(test)]
+    pub(crate) fn test(domain: &str, owner: &str) -> Self {
+        Self::new(domain, owner, OwnerStatus::Ledger, "000800", NamingService::XNS)
+    }
+
+    /// Whether this result was served stale from the cache
+    pub fn is_stale(&self) -> bool {
+        self.stale_age.is_some()
+    }
+
+    /// Owner address, if it could be determined
+    pub fn known_owner(&self) -> Option<&str> {
+        match self.owner_status {
+            OwnerStatus::Unknown => None,
+            _ => Some(&self.owner),
+        }
+    }
+}
+
+/// A name's registration with one naming service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Registration {
+    pub service: NamingService,
+
+    /// Owner XRPL address (empty when `owner_status` is `Unknown`)
+    pub owner: String,
+
+    #[serde(default)]
+    pub owner_status: OwnerStatus,
+
+    pub nft_id: String,
+}
+
+impl From<&DomainInfo> for Registration {
+    fn from(info: &DomainInfo) -> Self {
+        Self {
+            service: info.service.clone(),
+            owner: info.owner.clone(),
+            owner_status: info.owner_status,
+            nft_id: info.nft_id.clone(),
+        }
+    }
+}
+
+/// Expiration state of a resolved name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryStatus {
+    /// Not expired, or no expiration is recorded
+    #[default]
+    Active,
+
+    /// Expired, but within the configured grace period
+    GracePeriod,
+
+    /// Expired and past the grace period
+    Expired,
+}
+
+/// Reason a resolved name may be impersonating another one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum DomainWarning {
+    /// A label mixes scripts, e.g. Latin and Cyrillic letters
+    MixedScript,
+
+    /// Visually confusable with a different allowlisted or known name
+    ConfusableWith(String),
+}
+
+impl fmt::Display for DomainWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DomainWarning::MixedScript => f.write_str("mixes scripts"),
+            DomainWarning::ConfusableWith(name) => write!(f, "looks like {}", name),
+        }
+    }
+}
+
+/// How the owner of a domain NFT was determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnerStatus {
+    /// Reported by Clio `nft_info`
+    Clio,
+    /// Confirmed from the owner's NFToken pages on the ledger
+    Ledger,
+    /// Every owner lookup failed; only returned when the resolver is
+    /// configured to allow unknown owners
+    #[default]
+    Unknown,
+}
+
+/// Outcome of [`XnsResolver::resolve_many`](crate::XnsResolver::resolve_many)
+#[derive(Debug)]
+pub struct BatchResolution {
+    /// Per-domain results, in the same order as the input
+    pub results: Vec<XnsResult<DomainInfo>>,
+
+    /// Aggregate statistics for the batch
+    pub stats: BatchStats,
+}
+
+/// Aggregate statistics for a batch resolution
+#[derive(Debug, Clone, Default)]
+pub struct BatchStats {
+    /// Number of domains requested
+    pub total: usize,
+
+    /// Number of distinct valid domains after deduplication
+    pub unique: usize,
+
+    /// Domains resolved successfully
+    pub resolved: usize,
+
+    /// Domains not registered with any naming service
+    pub not_found: usize,
+
+    /// Domains refused as expired
+    pub expired: usize,
+
+    /// Domains that failed for another reason (e.g. invalid format)
+    pub failed: usize,
+
+    /// Distinct domains served from the cache
+    pub cache_hits: usize,
+
+    /// Issuer collections listed
+    pub issuers_listed: usize,
+
+    /// NFTs returned by the issuer listings
+    pub nfts_scanned: usize,
+
+    /// Metadata documents fetched
+    pub metadata_fetched: usize,
+
+    /// Wall-clock time for the whole batch
+    pub elapsed: Duration,
+}
+
+/// Current Unix time in seconds
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
 }
 
 /// NFT metadata structure
@@ -116,6 +501,14 @@ pub struct XrplNft {
 
     #[serde(rename = "Issuer")]
     pub issuer: Option<String>,
+
+    #[serde(rename = "NFTokenTaxon", default)]
+    pub taxon: Option<u32>,
+
+    /// Account believed to hold the NFT when it was listed (the queried
+    /// account for `account_nfts`, Clio's owner for `nfts_by_issuer`)
+    #[serde(skip)]
+    pub owner: Option<String>,
 }
 
 /// XRPL RPC request
@@ -143,6 +536,85 @@ pub struct AccountNftsResult {
     pub marker: Option<String>,
 }
 
+/// NFTokenPage ledger object
+#[derive(Debug, Deserialize)]
+pub struct NftPage {
+    #[serde(rename = "NFTokens", default)]
+    pub nf_tokens: Vec<NftPageEntry>,
+
+    /// Index of the preceding page of the same owner
+    #[serde(rename = "PreviousPageMin", default)]
+    pub previous_page_min: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NftPageEntry {
+    #[serde(rename = "NFToken")]
+    pub nf_token: XrplNft,
+}
+
+/// XLS-40 DID ledger object
+///
+/// `URI`, `Data` and `DIDDocument` are hex-encoded, up to 256 bytes each.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DidObject {
+    #[serde(rename = "Account")]
+    pub account: String,
+
+    #[serde(rename = "URI", default)]
+    pub uri: Option<String>,
+
+    #[serde(rename = "Data", default)]
+    pub data: Option<String>,
+
+    #[serde(rename = "DIDDocument", default)]
+    pub did_document: Option<String>,
+
+    /// Ledger of the transaction that last set the DID
+    #[serde(rename = "PreviousTxnLgrSeq", default)]
+    pub previous_txn_lgr_seq: Option<u32>,
+}
+
+/// account_tx result
+#[derive(Debug, Deserialize)]
+pub struct AccountTxResult {
+    pub transactions: Vec<AccountTransaction>,
+
+    #[serde(default)]
+    pub marker: Option<serde_json::Value>,
+}
+
+/// Transaction and metadata from account_tx
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountTransaction {
+    #[serde(default, alias = "tx_json")]
+    pub tx: serde_json::Value,
+
+    #[serde(default)]
+    pub meta: serde_json::Value,
+
+    /// Ledger of the transaction, beside `tx_json` in API v2
+    #[serde(default)]
+    pub ledger_index: Option<u32>,
+}
+
+/// Seconds between the Unix epoch and the XRPL epoch (2000-01-01)
+const RIPPLE_EPOCH_OFFSET: u64 = 946_684_800;
+
+impl AccountTransaction {
+    /// Ledger the transaction was validated in
+    pub fn ledger_index(&self) -> Option<u32> {
+        self.ledger_index.or_else(|| {
+            self.tx.get("ledger_index").and_then(|i| i.as_u64()).and_then(|i| u32::try_from(i).ok())
+        })
+    }
+
+    /// Close time of the transaction's ledger, as Unix time
+    pub fn timestamp(&self) -> Option<u64> {
+        self.tx.get("date").and_then(|d| d.as_u64()).map(|d| d + RIPPLE_EPOCH_OFFSET)
+    }
+}
+
 /// nft_info result (from Clio)
 #[derive(Debug, Clone, Deserialize)]
 pub struct NftInfo {
@@ -0,0 +1,445 @@
+//! Ledger-derived NFT ownership
+//!
+//! Many rippled servers do not run Clio, so `nft_info` is unavailable. The
+//! owner of an NFToken can still be established from rippled alone:
+//!
+//! - whether an account holds an NFT is answered with `ledger_entry`, walking
+//!   its NFTokenPage objects from the last page down to the page whose range
+//!   covers the NFTokenID;
+//! - where an NFT went is read from the NFTokenPage changes recorded in the
+//!   metadata of NFTokenMint, NFTokenAcceptOffer and NFTokenBurn
+//!   transactions, found through `account_tx`.
+//!
+//! Tracing starts at a likely holder (the last known one, or the issuer) and
+//! follows each transfer until an account that still holds the NFT is found.
+
+use crate::address::{decode_account_id, encode_account_id_with_prefix};
+use crate::client::XrplClient;
+use crate::error::{XnsError, XnsResult};
+use crate::models::AccountTransaction;
+use serde_json::Value;
+
+/// Maximum number of transfers followed from the starting account
+const MAX_TRANSFER_HOPS: usize = 8;
+
+const HISTORY_PAGE_SIZE: u32 = 200;
+
+/// NFTokenPage objects visited before giving up on one account
+const MAX_PAGE_WALK: usize = 256;
+
+/// An NFT leaving an account
+#[derive(Debug, PartialEq, Eq)]
+enum Transfer {
+    To(String),
+    Burned,
+}
+
+/// Follow an NFT's transfers from `start` to its current holder
+///
+/// Returns `Ok(None)` when the trail ends without reaching an account that
+/// holds the NFT, and `DomainNotFound` when the NFT was burned.
+pub(crate) async fn trace_owner(
+    client: &XrplClient,
+    start: &str,
+    nft_id: &str,
+) -> XnsResult<Option<String>> {
+    let mut holder = start.to_string();
+
+    for _ in 0..MAX_TRANSFER_HOPS {
+        if holds_nft(client, &holder, nft_id).await? {
+            return Ok(Some(holder));
+        }
+
+        match last_transfer_out(client, &holder, nft_id).await? {
+            Some(Transfer::To(next)) => {
+                tracing::debug!("NFT {} moved from {} to {}", nft_id, holder, next);
+                holder = next;
+            }
+            Some(Transfer::Burned) => {
+                return Err(XnsError::DomainNotFound(format!("NFT {} has been burned", nft_id)));
+            }
+            None => return Ok(None),
+        }
+    }
+
+    tracing::warn!("Gave up tracing NFT {} after {} transfers", nft_id, MAX_TRANSFER_HOPS);
+    Ok(None)
+}
+
+/// Check whether `account` holds an NFT by walking its NFTokenPages
+///
+/// A page keyed `account ‖ K` holds the tokens whose low 96 bits fall in
+/// `[PreviousPageMin, K)`, and the last page is always keyed with all ones,
+/// so the walk starts there and steps back until it reaches the covering page.
+pub(crate) async fn holds_nft(client: &XrplClient, account: &str, nft_id: &str) -> XnsResult<bool> {
+    if nft_id.len() != 64 {
+        return Err(XnsError::InvalidInput(format!("Invalid NFTokenID: {}", nft_id)));
+    }
+
+    let account_id = hex::encode_upper(decode_account_id(account)?);
+    let target = format!("{}{}", account_id, nft_id[40..].to_uppercase());
+    let mut index = format!("{}{}", account_id, "F".repeat(24));
+
+    for _ in 0..MAX_PAGE_WALK {
+        let Some(page) = client.nft_page(&index).await? else {
+            return Ok(false);
+        };
+
+        match page.previous_page_min.map(|min| min.to_uppercase()) {
+            Some(previous) if previous > target => index = previous,
+            _ => {
+                return Ok(page
+                    .nf_tokens
+                    .iter()
+                    .any(|entry| entry.nf_token.nft_token_id.eq_ignore_ascii_case(nft_id)));
+            }
+        }
+    }
+
+    Err(XnsError::InternalError(format!(
+        "{} has more than {} NFToken pages",
+        account, MAX_PAGE_WALK
+    )))
+}
+
+/// Find the most recent transaction that moved the NFT out of `account`
+///
+/// Reads up to [`XrplClient::history_pages`] pages of history.
+async fn last_transfer_out(
+    client: &XrplClient,
+    account: &str,
+    nft_id: &str,
+) -> XnsResult<Option<Transfer>> {
+    let prefix = client.network().address_prefix();
+    let mut marker = None;
+
+    for _ in 0..client.history_pages() {
+        let page = client.account_tx(account, marker, HISTORY_PAGE_SIZE).await?;
+
+        if let Some(transfer) = page
+            .transactions
+            .iter()
+            .find_map(|entry| transfer_out(entry, account, nft_id, prefix))
+        {
+            return Ok(Some(transfer));
+        }
+
+        marker = page.marker;
+        if marker.is_none() {
+            return Ok(None);
+        }
+    }
+
+    history_exhausted(client, account, nft_id);
+    Ok(None)
+}
+
+/// Ledger of the most recent transaction that moved the NFT into `account`
+///
+/// Returns `Ok(None)` when no such transaction is within the scanned history,
+/// which reads up to [`XrplClient::history_pages`] pages.
+pub(crate) async fn acquired_ledger(client: &XrplClient, account: &str, nft_id: &str) -> XnsResult<Option<u32>> {
+    let prefix = client.network().address_prefix();
+    let mut marker = None;
+
+    for _ in 0..client.history_pages() {
+        let page = client.account_tx(account, marker, HISTORY_PAGE_SIZE).await?;
+
+        if let Some(entry) = page
+            .transactions
+            .iter()
+            .find(|entry| transfer_in(entry, account, nft_id, prefix))
+        {
+            return Ok(entry.ledger_index());
+        }
+
+        marker = page.marker;
+        if marker.is_none() {
+            return Ok(None);
+        }
+    }
+
+    history_exhausted(client, account, nft_id);
+    Ok(None)
+}
+
+/// Log a history scan that stopped before reaching the account's first
+/// transaction
+pub(crate) fn history_exhausted(client: &XrplClient, account: &str, nft_id: &str) {
+    tracing::warn!(
+        "No transfer of NFT {} in the last {} pages of {}'s history; raise history_pages to scan further",
+        nft_id,
+        client.history_pages(),
+        account
+    );
+}
+
+/// Accounts whose NFTokenPages held an NFT before and after a transaction
+#[derive(Debug, Default)]
+struct Holders {
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+/// Read an NFT's holders from a successful transaction's NFTokenPage changes
+fn holders(entry: &AccountTransaction, nft_id: &str, prefix: u8) -> Option<Holders> {
+    if entry.meta.get("TransactionResult").and_then(Value::as_str) != Some("tesSUCCESS") {
+        return None;
+    }
+
+    let contains = |fields: Option<&Value>| {
+        fields
+            .and_then(|f| f.get("NFTokens"))
+            .and_then(Value::as_array)
+            .is_some_and(|tokens| {
+                tokens.iter().any(|token| {
+                    token
+                        .pointer("/NFToken/NFTokenID")
+                        .and_then(Value::as_str)
+                        .is_some_and(|id| id.eq_ignore_ascii_case(nft_id))
+                })
+            })
+    };
+
+    let mut holders = Holders::default();
+
+    for node in entry.meta.get("AffectedNodes")?.as_array()? {
+        let Some((kind, body)) = node.as_object().and_then(|n| n.iter().next()) else {
+            continue;
+        };
+        if body.get("LedgerEntryType").and_then(Value::as_str) != Some("NFTokenPage") {
+            continue;
+        }
+
+        // Only changed fields appear in PreviousFields
+        let final_fields = body.get("FinalFields");
+        let previous_fields = body
+            .get("PreviousFields")
+            .filter(|f| f.get("NFTokens").is_some())
+            .or(final_fields);
+
+        let (before, after) = match kind.as_str() {
+            "CreatedNode" => (false, contains(body.get("NewFields"))),
+            "ModifiedNode" => (contains(previous_fields), contains(final_fields)),
+            "DeletedNode" => (contains(previous_fields), false),
+            _ => continue,
+        };
+        if !before && !after {
+            continue;
+        }
+
+        let Some(owner) = body
+            .get("LedgerIndex")
+            .and_then(Value::as_str)
+            .and_then(|index| page_owner(index, prefix))
+        else {
+            continue;
+        };
+
+        if before && !holders.before.contains(&owner) {
+            holders.before.push(owner.clone());
+        }
+        if after && !holders.after.contains(&owner) {
+            holders.after.push(owner);
+        }
+    }
+
+    Some(holders)
+}
+
+/// Owner of an NFTokenPage, from the account ID prefix of its index
+fn page_owner(index: &str, prefix: u8) -> Option<String> {
+    let bytes = hex::decode(index.get(..40)?).ok()?;
+    Some(encode_account_id_with_prefix(prefix, bytes.as_slice().try_into().ok()?))
+}
+
+/// Whether a transaction moved the NFT into `account`, by mint or transfer
+pub(crate) fn transfer_in(entry: &AccountTransaction, account: &str, nft_id: &str, prefix: u8) -> bool {
+    holders(entry, nft_id, prefix).is_some_and(|holders| {
+        holders.after.iter().any(|a| a == account) && !holders.before.iter().any(|a| a == account)
+    })
+}
+
+/// Interpret a transaction as the NFT leaving `account`, if it is one
+///
+/// Page owners are encoded with the network's address `prefix` so they
+/// compare equal to `account`.
+fn transfer_out(
+    entry: &AccountTransaction,
+    account: &str,
+    nft_id: &str,
+    prefix: u8,
+) -> Option<Transfer> {
+    let holders = holders(entry, nft_id, prefix)?;
+
+    if holders.after.iter().any(|a| a == account) {
+        return None;
+    }
+
+    match holders.after.first() {
+        // Sold or given away by `account`, or minted straight to an
+        // authorized minter when `account` is the issuer
+        Some(next) if holders.before.is_empty() || holders.before.iter().any(|a| a == account) => {
+            Some(Transfer::To(next.clone()))
+        }
+        Some(_) => None,
+        None if !holders.before.is_empty() => Some(Transfer::Burned),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::{ACCOUNT_ID_PREFIX, encode_account_id};
+    use crate::mock_rpc::MockRpc;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const NFT: &str = "00080000B5F762798A53D543A014CAF8B297CFF8F2F937E80000099B00000000";
+
+    fn page(account_byte: u8) -> (String, String) {
+        let id = [account_byte; 20];
+        (encode_account_id(&id), format!("{}{}", hex::encode_upper(id), "F".repeat(24)))
+    }
+
+    fn tokens(ids: &[&str]) -> Value {
+        json!(ids.iter().map(|id| json!({ "NFToken": { "NFTokenID": id } })).collect::<Vec<_>>())
+    }
+
+    fn tx(tx_type: &str, nodes: Value) -> AccountTransaction {
+        AccountTransaction {
+            tx: json!({ "TransactionType": tx_type }),
+            meta: json!({ "TransactionResult": "tesSUCCESS", "AffectedNodes": nodes }),
+            ledger_index: None,
+        }
+    }
+
+    #[test]
+    fn test_transfer_out_of_accepted_offer() {
+        let (alice, alice_page) = page(0x11);
+        let (bob, bob_page) = page(0x22);
+
+        let sale = tx("NFTokenAcceptOffer", json!([
+            { "ModifiedNode": {
+                "LedgerEntryType": "NFTokenPage",
+                "LedgerIndex": alice_page,
+                "PreviousFields": { "NFTokens": tokens(&[NFT, "AA"]) },
+                "FinalFields": { "NFTokens": tokens(&["AA"]) }
+            }},
+            { "CreatedNode": {
+                "LedgerEntryType": "NFTokenPage",
+                "LedgerIndex": bob_page,
+                "NewFields": { "NFTokens": tokens(&[NFT]) }
+            }}
+        ]));
+
+        assert_eq!(transfer_out(&sale, &alice, NFT, ACCOUNT_ID_PREFIX), Some(Transfer::To(bob.clone())));
+        assert_eq!(transfer_out(&sale, &bob, NFT, ACCOUNT_ID_PREFIX), None);
+        assert_eq!(transfer_out(&sale, &alice, "00080000FFFF", ACCOUNT_ID_PREFIX), None);
+    }
+
+    #[test]
+    fn test_transfer_out_of_mint_and_burn() {
+        let (issuer, _) = page(0x11);
+        let (minter, minter_page) = page(0x33);
+
+        // Minted by an authorized minter on the issuer's behalf
+        let mint = tx("NFTokenMint", json!([
+            { "ModifiedNode": {
+                "LedgerEntryType": "NFTokenPage",
+                "LedgerIndex": minter_page,
+                "PreviousFields": { "NFTokens": tokens(&["AA"]) },
+                "FinalFields": { "NFTokens": tokens(&["AA", NFT]) }
+            }}
+        ]));
+        assert_eq!(transfer_out(&mint, &issuer, NFT, ACCOUNT_ID_PREFIX), Some(Transfer::To(minter.clone())));
+
+        let burn = tx("NFTokenBurn", json!([
+            { "DeletedNode": {
+                "LedgerEntryType": "NFTokenPage",
+                "LedgerIndex": minter_page,
+                "FinalFields": { "NFTokens": tokens(&[NFT]) }
+            }}
+        ]));
+        assert_eq!(transfer_out(&burn, &minter, NFT, ACCOUNT_ID_PREFIX), Some(Transfer::Burned));
+
+        let failed = AccountTransaction {
+            meta: json!({ "TransactionResult": "tecNO_PERMISSION" }),
+            ..burn
+        };
+        assert_eq!(transfer_out(&failed, &minter, NFT, ACCOUNT_ID_PREFIX), None);
+    }
+
+    #[tokio::test]
+    async fn test_holds_nft_at_page_boundary() {
+        let (alice, _) = page(0x11);
+        let account_id = hex::encode_upper([0x11; 20]);
+        // The first page's key equals the low 96 bits of NFT, so NFT sorts
+        // onto the last page
+        let first_page = format!("{}{}", account_id, &NFT[40..]);
+        let last_page = format!("{}{}", account_id, "F".repeat(24));
+        let below = format!("{}{}", &NFT[..40], "00000000B5F762798A53D543");
+
+        let pages = [
+            (last_page.clone(), json!({ "PreviousPageMin": first_page, "NFTokens": tokens(&[NFT]) })),
+            (first_page.clone(), json!({ "NFTokens": tokens(&[below.as_str()]) })),
+        ];
+        let rpc = MockRpc::start(move |method, params| {
+            assert_eq!(method, "ledger_entry");
+            match pages.iter().find(|(index, _)| params["nft_page"] == *index) {
+                Some((_, node)) => json!({ "node": node }),
+                None => json!({ "error": "entryNotFound" }),
+            }
+        })
+        .await;
+        let client = XrplClient::new(rpc.network());
+
+        assert!(holds_nft(&client, &alice, NFT).await.unwrap());
+        assert!(holds_nft(&client, &alice, &below).await.unwrap());
+        let missing = format!("{}{}", &NFT[..40], "0000000000000000000000FF");
+        assert!(!holds_nft(&client, &alice, &missing).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_history_scan_reads_configured_pages() {
+        let (alice, _) = page(0x11);
+        let pages = Arc::new(AtomicUsize::new(0));
+        let served = pages.clone();
+        let rpc = MockRpc::start(move |method, _| {
+            assert_eq!(method, "account_tx");
+            served.fetch_add(1, Ordering::SeqCst);
+            json!({ "transactions": [], "marker": "next" })
+        })
+        .await;
+        let client = XrplClient::new(rpc.network()).with_history_pages(3);
+
+        assert_eq!(acquired_ledger(&client, &alice, NFT).await.unwrap(), None);
+        assert_eq!(pages.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_transfer_in() {
+        let (alice, alice_page) = page(0x11);
+        let (bob, bob_page) = page(0x22);
+
+        let sale = tx("NFTokenAcceptOffer", json!([
+            { "DeletedNode": {
+                "LedgerEntryType": "NFTokenPage",
+                "LedgerIndex": alice_page,
+                "FinalFields": { "NFTokens": tokens(&[NFT]) }
+            }},
+            { "ModifiedNode": {
+                "LedgerEntryType": "NFTokenPage",
+                "LedgerIndex": bob_page,
+                "PreviousFields": { "NFTokens": tokens(&["AA"]) },
+                "FinalFields": { "NFTokens": tokens(&["AA", NFT]) }
+            }}
+        ]));
+
+        assert!(transfer_in(&sale, &bob, NFT, ACCOUNT_ID_PREFIX));
+        assert!(!transfer_in(&sale, &alice, NFT, ACCOUNT_ID_PREFIX));
+        assert!(!transfer_in(&sale, &bob, "00080000FFFF", ACCOUNT_ID_PREFIX));
+    }
+}
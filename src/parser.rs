@@ -1,16 +1,82 @@
+use crate::domain::DomainName;
 use crate::error::{XnsError, XnsResult};
 use crate::models::NftMetadata;
 use reqwest::Client;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Public IPFS gateways tried in order
+pub const DEFAULT_IPFS_GATEWAYS: [&str; 3] = [
+    "https://ipfs.io/ipfs/",
+    "https://gateway.pinata.cloud/ipfs/",
+    "https://cloudflare-ipfs.com/ipfs/",
+];
+
+/// Text record keys, by the lowercase spellings naming services use
+const TEXT_RECORD_KEYS: &[(&str, &str)] = &[
+    ("avatar", "avatar"),
+    ("profile image", "avatar"),
+    ("profile picture", "avatar"),
+    ("pfp", "avatar"),
+    ("url", "url"),
+    ("website", "url"),
+    ("homepage", "url"),
+    ("email", "email"),
+    ("e-mail", "email"),
+    ("description", "description"),
+    ("bio", "description"),
+    ("twitter", "twitter"),
+    ("x", "twitter"),
+    ("com.twitter", "twitter"),
+    ("github", "github"),
+    ("com.github", "github"),
+    ("discord", "discord"),
+    ("com.discord", "discord"),
+    ("telegram", "telegram"),
+    ("org.telegram", "telegram"),
+    ("reddit", "reddit"),
+    ("com.reddit", "reddit"),
+    ("instagram", "instagram"),
+    ("com.instagram", "instagram"),
+    ("linkedin", "linkedin"),
+    ("com.linkedin", "linkedin"),
+];
+
+/// Coins whose tickers name address attributes on their own (`"BTC"`)
+const COIN_TICKERS: &[&str] = &[
+    "xrp", "btc", "eth", "sol", "ltc", "doge", "bch", "ada", "xlm", "dot", "bnb", "trx", "atom", "avax",
+    "matic", "pol", "xah", "flr", "sgb", "evm",
+];
+
+/// Address and text records carried in a name's NFT metadata
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataRecords {
+    /// Addresses by lowercase coin ticker
+    pub addresses: HashMap<String, String>,
+
+    /// Text records by canonical key (`avatar`, `url`, `email`, ...)
+    pub text_records: HashMap<String, String>,
+}
 
 /// NFT metadata parser
 pub struct MetadataParser {
     client: Client,
+    ipfs_gateways: Vec<String>,
 }
 
 impl MetadataParser {
     pub fn new() -> Self {
+        Self::with_client(
+            Client::new(),
+            DEFAULT_IPFS_GATEWAYS.iter().map(|g| g.to_string()).collect(),
+        )
+    }
+
+    /// Create with a custom HTTP client and IPFS gateway list
+    pub fn with_client(client: Client, ipfs_gateways: Vec<String>) -> Self {
         Self {
-            client: Client::new(),
+            client,
+            ipfs_gateways,
         }
     }
 
@@ -47,17 +113,11 @@ impl MetadataParser {
             XnsError::MetadataError("Invalid IPFS URI".to_string())
         })?;
 
-        // Use public IPFS gateways
-        let gateways = [
-            format!("https://ipfs.io/ipfs/{}", cid),
-            format!("https://gateway.pinata.cloud/ipfs/{}", cid),
-            format!("https://cloudflare-ipfs.com/ipfs/{}", cid),
-        ];
-
         let mut last_error = None;
 
-        for gateway_url in &gateways {
-            match self.fetch_from_http(gateway_url).await {
+        for gateway in &self.ipfs_gateways {
+            let gateway_url = format!("{}/{}", gateway.trim_end_matches('/'), cid);
+            match self.fetch_from_http(&gateway_url).await {
                 Ok(metadata) => return Ok(metadata),
                 Err(e) => {
                     tracing::warn!("IPFS gateway {} failed: {}", gateway_url, e);
@@ -95,53 +155,133 @@ impl MetadataParser {
         })
     }
 
-    /// Extract domain name from metadata
-    pub fn extract_domain_name(metadata: &NftMetadata) -> Option<String> {
-        // Try name field first
+    /// Extract and normalize the domain name from metadata
+    ///
+    /// Names under any TLD are returned; the resolver keeps those served by
+    /// the provider that issued the NFT.
+    pub fn extract_domain_name(metadata: &NftMetadata) -> Option<DomainName> {
         // XNS format: "ckelley.xrp, an XRPNS name" or just "ckelley.xrp"
-        if metadata.name.contains(".xrp") {
-            // Extract domain before comma or use entire name
-            let domain = metadata.name
-                .split(',')
-                .next()
-                .unwrap_or(&metadata.name)
-                .trim();
-
-            if domain.ends_with(".xrp") {
-                return Some(domain.to_string());
-            }
+        let parse = |text: &str| DomainName::parse(text.split(',').next().unwrap_or(text)).ok();
+
+        // Try name field first
+        if let Some(domain) = parse(&metadata.name) {
+            return Some(domain);
         }
 
         // Check attributes for domain field
         for attr in &metadata.attributes {
-            if attr.trait_type == "domain" || attr.trait_type == "name" || attr.trait_type == "Domain" {
-                if let Some(domain) = attr.value.as_str() {
-                    if domain.contains(".xrp") {
-                        let domain = domain.split(',').next().unwrap_or(domain).trim();
-                        if domain.ends_with(".xrp") {
-                            return Some(domain.to_string());
-                        }
-                    }
-                }
+            if (attr.trait_type == "domain" || attr.trait_type == "name" || attr.trait_type == "Domain")
+                && let Some(domain) = attr.value.as_str().and_then(parse)
+            {
+                return Some(domain);
             }
         }
 
         // Check extra fields
-        if let Some(domain) = metadata.extra.get("domain") {
-            if let Some(domain_str) = domain.as_str() {
-                if domain_str.contains(".xrp") {
-                    let domain = domain_str.split(',').next().unwrap_or(domain_str).trim();
-                    if domain.ends_with(".xrp") {
-                        return Some(domain.to_string());
+        if let Some(domain) = metadata.extra.get("domain").and_then(|d| d.as_str()).and_then(parse) {
+            return Some(domain);
+        }
+
+        None
+    }
+
+    /// Extract address and text records from metadata
+    ///
+    /// Records are read from attributes (`{"trait_type": "BTC Address",
+    /// "value": ...}`, `"Twitter"`, ...), as XNS publishes them, and from
+    /// top-level fields: `addresses` as an object or as the XRP Domains list
+    /// of `{symbol, address}`, `records` or `text_records` objects, the XRP
+    /// Domains `profile_info` object, and plain fields such as `avatar` or
+    /// `email`. Attributes win over top-level fields.
+    pub fn extract_records(metadata: &NftMetadata) -> MetadataRecords {
+        let mut records = MetadataRecords::default();
+
+        let mut fields: Vec<(&String, &Value)> = metadata.extra.iter().collect();
+        // Deterministic order when several fields name the same record
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (key, value) in fields {
+            match (key.to_lowercase().as_str(), value) {
+                ("addresses", Value::Object(addresses)) => {
+                    for (coin, address) in addresses {
+                        if let Some(address) = text_value(address) {
+                            records.addresses.insert(coin.to_lowercase(), address);
+                        }
+                    }
+                }
+                ("addresses", Value::Array(addresses)) => {
+                    for entry in addresses {
+                        let coin = entry.get("symbol").or_else(|| entry.get("coin")).and_then(Value::as_str);
+                        if let (Some(coin), Some(address)) = (coin, entry.get("address").and_then(text_value)) {
+                            records.addresses.insert(coin.to_lowercase(), address);
+                        }
                     }
                 }
+                ("records" | "text_records" | "profile_info", Value::Object(entries)) => {
+                    for (key, value) in entries {
+                        if let (Some(key), Some(value)) = (Self::text_record_key(key), text_value(value)) {
+                            records.text_records.insert(key.to_string(), value);
+                        }
+                    }
+                }
+                (key, value) => records.insert(key, value),
             }
         }
 
-        None
+        for attribute in &metadata.attributes {
+            records.insert(&attribute.trait_type, &attribute.value);
+        }
+
+        records
+    }
+
+    /// Canonical text record key for a key written by a naming service
+    pub fn text_record_key(key: &str) -> Option<&'static str> {
+        let key = key.trim().to_lowercase();
+        TEXT_RECORD_KEYS
+            .iter()
+            .find(|(spelling, _)| *spelling == key)
+            .map(|(_, canonical)| *canonical)
+    }
+}
+
+impl MetadataRecords {
+    /// Record a single `key: value` pair, if the key names a record
+    fn insert(&mut self, key: &str, value: &Value) {
+        let Some(value) = text_value(value) else {
+            return;
+        };
+
+        if let Some(key) = MetadataParser::text_record_key(key) {
+            self.text_records.insert(key.to_string(), value);
+        } else if let Some(coin) = address_coin(key) {
+            self.addresses.insert(coin, value);
+        }
     }
 }
 
+/// Coin named by an address key: `BTC`, `BTC Address`, `ETH wallet`,
+/// `address.eth` or `Address: SOL`
+fn address_coin(key: &str) -> Option<String> {
+    let key = key.trim().to_lowercase();
+
+    let coin = ["address", "wallet"]
+        .iter()
+        .find_map(|word| {
+            key.strip_suffix(word)
+                .or_else(|| key.strip_prefix(word))
+                .map(|rest| rest.trim_matches(|c: char| c.is_whitespace() || matches!(c, '.' | ':' | '_' | '-')))
+        })
+        .unwrap_or(&key);
+
+    (COIN_TICKERS.contains(&coin)).then(|| coin.to_string())
+}
+
+/// Non-empty string value, trimmed
+fn text_value(value: &Value) -> Option<String> {
+    value.as_str().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
 impl Default for MetadataParser {
     fn default() -> Self {
         Self::new()
@@ -163,11 +303,42 @@ mod tests {
         };
 
         assert_eq!(
-            MetadataParser::extract_domain_name(&metadata),
-            Some("ckelley.xrp".to_string())
+            MetadataParser::extract_domain_name(&metadata).as_ref().map(DomainName::as_str),
+            Some("ckelley.xrp")
         );
     }
 
+    #[test]
+    fn test_extract_records() {
+        let metadata: NftMetadata = serde_json::from_value(serde_json::json!({
+            "name": "ckelley.xrp",
+            "attributes": [
+                { "trait_type": "BTC Address", "value": "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh" },
+                { "trait_type": "ETH", "value": " 0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb " },
+                { "trait_type": "Website", "value": "https://ghostkellz.sh" },
+                { "trait_type": "Length", "value": 7 },
+                { "trait_type": "Twitter", "value": "" }
+            ],
+            "addresses": [{ "symbol": "SOL", "address": "So1ana" }],
+            "profile_info": { "twitter": "@ckelley", "github": "ghostkellz" },
+            "avatar": "ipfs://avatar",
+            "email": "ckelley@ghostkellz.sh",
+            "image": "ipfs://image"
+        }))
+        .unwrap();
+
+        let records = MetadataParser::extract_records(&metadata);
+        assert_eq!(records.addresses.len(), 3);
+        assert_eq!(records.addresses["eth"], "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        assert_eq!(records.addresses["sol"], "So1ana");
+        assert_eq!(records.text_records["url"], "https://ghostkellz.sh");
+        assert_eq!(records.text_records["twitter"], "@ckelley");
+        assert_eq!(records.text_records["github"], "ghostkellz");
+        assert_eq!(records.text_records["avatar"], "ipfs://avatar");
+        assert_eq!(records.text_records["email"], "ckelley@ghostkellz.sh");
+        assert_eq!(records.text_records.len(), 5);
+    }
+
     #[test]
     fn test_hex_decode() {
         let hex_uri = hex::encode("https://example.com/metadata.json");
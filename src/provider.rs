@@ -0,0 +1,296 @@
+//! Naming service providers
+//!
+//! A [`NameServiceProvider`] answers for one naming service: which TLDs it
+//! serves, so the resolver can route each name by suffix, how to resolve
+//! names and find the names an account holds, and how to fill in records the
+//! NFT metadata lacks. XNS and XRP Domains are built in; further XRPL naming
+//! services, or private ones, are registered with
+//! [`XnsResolverBuilder::provider`](crate::XnsResolverBuilder::provider).
+//!
+//! Most XRPL naming services mint each name as an NFT from a known issuer.
+//! Such a service only needs to name its issuers: the default `resolve` and
+//! `reverse` scan the issuers' NFTs with the resolver's cache, metadata
+//! limits and owner lookups.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use xns_sdk_rs::{IssuerProvider, NamingService, XnsResolver};
+//!
+//! # async fn example() -> xns_sdk_rs::XnsResult<()> {
+//! let resolver = XnsResolver::builder()
+//!     .provider(Arc::new(IssuerProvider::new(
+//!         NamingService::Custom("acme".to_string()),
+//!         vec!["rYhfynZDrde1uSvvQAYctApg6DnVE5HKm".to_string()],
+//!     )))
+//!     .build()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::domain::{DomainName, XRP_TLD, normalize_tld};
+use crate::error::XnsResult;
+use crate::issuers::Issuer;
+use crate::models::{DomainInfo, NamingService};
+use crate::parser::MetadataParser;
+use crate::records::{Record, RecordSource};
+use crate::resolver::XnsResolver;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// A naming service the resolver can query
+#[async_trait]
+pub trait NameServiceProvider: Send + Sync {
+    /// Service reported in [`DomainInfo::service`]
+    fn service(&self) -> NamingService;
+
+    /// Whether names under `tld` (normalized, without a dot) can belong to
+    /// this service
+    fn supports_tld(&self, tld: &str) -> bool {
+        tld == XRP_TLD
+    }
+
+    /// Accounts that mint this service's name NFTs
+    ///
+    /// Batch resolution lists these once for a whole batch instead of
+    /// calling [`resolve`](Self::resolve) per name. Return nothing when
+    /// names are not NFTs from fixed issuers.
+    fn issuers(&self) -> &[Issuer] {
+        &[]
+    }
+
+    /// Resolve a registered name, failing with `DomainNotFound` when the
+    /// service does not know it
+    async fn resolve(&self, resolver: &XnsResolver, domain: &DomainName) -> XnsResult<DomainInfo> {
+        resolver.resolve_issued(self.service(), self.issuers(), domain).await
+    }
+
+    /// Names of this service held by an account
+    async fn reverse(&self, resolver: &XnsResolver, address: &str) -> XnsResult<Vec<DomainName>> {
+        let names = resolver.names_held_by(address, self.issuers()).await?;
+        Ok(names.into_iter().filter(|name| self.supports_tld(name.tld())).collect())
+    }
+
+    /// Add records from outside the NFT metadata to a resolved name
+    ///
+    /// Failures are logged and the name is returned without them.
+    async fn enrich(&self, _resolver: &XnsResolver, _info: &mut DomainInfo) -> XnsResult<()> {
+        Ok(())
+    }
+}
+
+/// A service whose names are NFTs minted by a fixed set of issuers
+///
+/// Used for XNS and for services configured only by their issuers. Serves
+/// `.xrp` names unless other TLDs are set.
+#[derive(Debug, Clone)]
+pub struct IssuerProvider {
+    service: NamingService,
+    issuers: Vec<Issuer>,
+    tlds: Vec<String>,
+}
+
+impl IssuerProvider {
+    pub fn new<I>(service: NamingService, issuers: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Issuer>,
+    {
+        Self {
+            service,
+            issuers: issuers.into_iter().map(Into::into).collect(),
+            tlds: vec![XRP_TLD.to_string()],
+        }
+    }
+
+    /// Serve names under these TLDs instead of `.xrp`; invalid TLDs are
+    /// ignored
+    pub fn with_tlds<I, S>(mut self, tlds: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.tlds = tlds.into_iter().filter_map(|tld| normalize_tld(tld.as_ref()).ok()).collect();
+        self
+    }
+}
+
+#[async_trait]
+impl NameServiceProvider for IssuerProvider {
+    fn service(&self) -> NamingService {
+        self.service.clone()
+    }
+
+    fn supports_tld(&self, tld: &str) -> bool {
+        self.tlds.iter().any(|t| t == tld)
+    }
+
+    fn issuers(&self) -> &[Issuer] {
+        &self.issuers
+    }
+}
+
+/// XRP Domains (xrpdomains.xyz)
+///
+/// Names are resolved from the ledger like any issued name, then enriched
+/// with the addresses and profile records served by the XRP Domains API.
+#[derive(Debug, Clone)]
+pub struct XrpDomainsProvider {
+    issuers: Vec<Issuer>,
+    tlds: Vec<String>,
+}
+
+impl XrpDomainsProvider {
+    const API_URL: &'static str = "https://app.xrpdomains.xyz/api/xrplnft/getAddress";
+
+    pub fn new<I>(issuers: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Issuer>,
+    {
+        Self {
+            issuers: issuers.into_iter().map(Into::into).collect(),
+            tlds: vec![XRP_TLD.to_string()],
+        }
+    }
+
+    /// Serve names under these TLDs instead of `.xrp`; invalid TLDs are
+    /// ignored
+    pub fn with_tlds<I, S>(mut self, tlds: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.tlds = tlds.into_iter().filter_map(|tld| normalize_tld(tld.as_ref()).ok()).collect();
+        self
+    }
+}
+
+#[async_trait]
+impl NameServiceProvider for XrpDomainsProvider {
+    fn service(&self) -> NamingService {
+        NamingService::XRPDomains
+    }
+
+    fn supports_tld(&self, tld: &str) -> bool {
+        self.tlds.iter().any(|t| t == tld)
+    }
+
+    fn issuers(&self) -> &[Issuer] {
+        &self.issuers
+    }
+
+    async fn enrich(&self, resolver: &XnsResolver, info: &mut DomainInfo) -> XnsResult<()> {
+        tracing::debug!("Fetching XRP Domains API data for {}", info.domain);
+
+        let response = resolver
+            .http()
+            .get(Self::API_URL)
+            .query(&[("domain", &info.domain)])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Ok(());
+        }
+
+        let api_data: serde_json::Value = response.json().await?;
+        let Some(data) = api_data.get("data") else {
+            return Ok(());
+        };
+
+        if let Some(addresses) = data.get("addresses").and_then(|a| a.as_array()) {
+            for addr in addresses {
+                if let (Some(symbol), Some(address)) = (
+                    addr.get("symbol").and_then(|s| s.as_str()),
+                    addr.get("address").and_then(|a| a.as_str()),
+                ) {
+                    info.add_address(symbol, Record::new(address, RecordSource::Service(self.service())));
+                }
+            }
+        }
+
+        if let Some(profile) = data.get("profile_info").and_then(|p| p.as_object()) {
+            for (field, value) in profile {
+                if let (Some(key), Some(value)) = (MetadataParser::text_record_key(field), value.as_str()) {
+                    info.add_text_record(key, Record::new(value, RecordSource::Service(self.service())));
+                }
+            }
+        }
+
+        tracing::info!("Enhanced {} with XRP Domains API data", info.domain);
+        Ok(())
+    }
+}
+
+/// Provider for a service listed in the config without a registered provider
+pub(crate) fn builtin(
+    service: NamingService,
+    issuers: Vec<Issuer>,
+    tlds: Vec<String>,
+) -> Arc<dyn NameServiceProvider> {
+    match service {
+        NamingService::XRPDomains => Arc::new(XrpDomainsProvider::new(issuers).with_tlds(tlds)),
+        service => Arc::new(IssuerProvider::new(service, issuers).with_tlds(tlds)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::XnsError;
+    use crate::models::OwnerStatus;
+
+    /// Resolves names from a fixed table, without touching the network
+    struct StaticProvider;
+
+    #[async_trait]
+    impl NameServiceProvider for StaticProvider {
+        fn service(&self) -> NamingService {
+            NamingService::Custom("static".to_string())
+        }
+
+        async fn resolve(&self, _resolver: &XnsResolver, domain: &DomainName) -> XnsResult<DomainInfo> {
+            if domain.as_str() != "alice.xrp" {
+                return Err(XnsError::DomainNotFound(domain.to_string()));
+            }
+
+            Ok(DomainInfo::new(domain.to_string(), "rAlice", OwnerStatus::Unknown, "", self.service()))
+        }
+
+        async fn enrich(&self, _resolver: &XnsResolver, info: &mut DomainInfo) -> XnsResult<()> {
+            info.text_records.insert("source".to_string(), "static".to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_provider() {
+        let resolver = XnsResolver::builder()
+            .services(Vec::new())
+            .provider(Arc::new(StaticProvider))
+            .build()
+            .await
+            .unwrap();
+
+        let info = resolver.resolve("Alice.xrp").await.unwrap();
+        assert_eq!(info.service, NamingService::Custom("static".to_string()));
+        assert_eq!(info.text_records.get("source").map(String::as_str), Some("static"));
+
+        let batch = resolver.resolve_many(&["alice.xrp", "bob.xrp"]).await;
+        assert_eq!(batch.stats.resolved, 1);
+        assert_eq!(batch.stats.not_found, 1);
+        assert_eq!(batch.stats.issuers_listed, 0);
+    }
+
+    #[test]
+    fn test_service_names() {
+        for (service, name) in [
+            (NamingService::XNS, "\"xns\""),
+            (NamingService::XRPDomains, "\"xrpdomains\""),
+            (NamingService::Custom("acme".to_string()), "\"acme\""),
+        ] {
+            assert_eq!(serde_json::to_string(&service).unwrap(), name);
+            assert_eq!(serde_json::from_str::<NamingService>(name).unwrap(), service);
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Typed name records and their precedence
+//!
+//! A name's addresses and text records can come from its NFT metadata, a
+//! naming service's API, memos sent by the owner or the owner's DID. Every
+//! value found is kept in [`DomainInfo::records`] as a [`Record`] carrying
+//! its source, when it was set and whether the name's current owner set it.
+//! A [`RecordPrecedence`] orders the values of each record; the first one is
+//! copied into `DomainInfo::addresses` or `DomainInfo::text_records`, and
+//! records whose sources disagree are listed by [`DomainInfo::conflicts`].
+
+use crate::models::{DomainInfo, NamingService};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Where a record of a resolved name came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordSource {
+    /// The name NFT's metadata
+    Metadata,
+
+    /// A naming service's off-ledger API
+    Service(NamingService),
+
+    /// Memo records published by the owner
+    Memo,
+
+    /// The owner's XLS-40 DID
+    Did,
+}
+
+impl RecordSource {
+    pub fn kind(&self) -> RecordSourceKind {
+        match self {
+            RecordSource::Metadata => RecordSourceKind::Metadata,
+            RecordSource::Service(_) => RecordSourceKind::Service,
+            RecordSource::Memo => RecordSourceKind::Memo,
+            RecordSource::Did => RecordSourceKind::Did,
+        }
+    }
+}
+
+/// A [`RecordSource`] without its naming service, for precedence lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordSourceKind {
+    Metadata,
+    Service,
+    Memo,
+    Did,
+}
+
+/// One value of a name record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub value: String,
+
+    pub source: RecordSource,
+
+    /// Ledger of the transaction that set the value, for on-ledger sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ledger_index: Option<u32>,
+
+    /// Unix time the value was set, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+
+    /// Whether the account that set the value owns the name
+    #[serde(default)]
+    pub set_by_owner: bool,
+}
+
+impl Record {
+    pub fn new(value: impl Into<String>, source: RecordSource) -> Self {
+        Self {
+            value: value.into(),
+            source,
+            ledger_index: None,
+            timestamp: None,
+            set_by_owner: false,
+        }
+    }
+
+    /// Set when and in which ledger the value was written
+    pub fn at(mut self, ledger_index: Option<u32>, timestamp: Option<u64>) -> Self {
+        self.ledger_index = ledger_index;
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Mark the value as written by the name's owner
+    pub fn by_owner(mut self, set_by_owner: bool) -> Self {
+        self.set_by_owner = set_by_owner;
+        self
+    }
+}
+
+/// Kind of name record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    Address,
+    Text,
+}
+
+/// A record with different values from different sources
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordConflict {
+    pub kind: RecordKind,
+
+    /// Coin or text record key
+    pub key: String,
+
+    /// Every value found, best first
+    pub values: Vec<Record>,
+}
+
+/// Every value found for a name's records, by key
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct NameRecords {
+    /// Address values by lowercase coin ticker
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub addresses: HashMap<String, Vec<Record>>,
+
+    /// Text record values by key
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub text_records: HashMap<String, Vec<Record>>,
+}
+
+impl NameRecords {
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.text_records.is_empty()
+    }
+}
+
+/// Order in which record values are preferred
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordPrecedence {
+    /// Sources from most to least preferred; unlisted sources come last
+    pub sources: Vec<RecordSourceKind>,
+
+    /// Prefer values set by the name's owner over any other source
+    pub owner_first: bool,
+}
+
+impl Default for RecordPrecedence {
+    fn default() -> Self {
+        Self {
+            sources: vec![
+                RecordSourceKind::Did,
+                RecordSourceKind::Memo,
+                RecordSourceKind::Service,
+                RecordSourceKind::Metadata,
+            ],
+            owner_first: true,
+        }
+    }
+}
+
+impl RecordPrecedence {
+    /// Sort values best first: by owner, then source, then newest
+    ///
+    /// The sort is stable, so values that tie keep the order they were
+    /// found in.
+    pub fn sort(&self, values: &mut [Record]) {
+        let rank = |source: &RecordSource| {
+            self.sources
+                .iter()
+                .position(|kind| *kind == source.kind())
+                .unwrap_or(self.sources.len())
+        };
+        values.sort_by_key(|record| {
+            (
+                self.owner_first && !record.set_by_owner,
+                rank(&record.source),
+                Reverse((record.ledger_index, record.timestamp)),
+            )
+        });
+    }
+}
+
+impl DomainInfo {
+    /// Add a value for an address record
+    ///
+    /// Takes effect in `addresses` once precedence is applied.
+    pub fn add_address(&mut self, coin: &str, record: Record) {
+        self.records.addresses.entry(coin.to_lowercase()).or_default().push(record);
+    }
+
+    /// Add a value for a text record
+    ///
+    /// Takes effect in `text_records` once precedence is applied.
+    pub fn add_text_record(&mut self, key: &str, record: Record) {
+        self.records.text_records.entry(key.to_string()).or_default().push(record);
+    }
+
+    /// Order every record's values and set `addresses` and `text_records`
+    /// to the preferred ones
+    ///
+    /// Entries set directly in `addresses` or `text_records`, without a
+    /// typed record, are kept, except addresses under another casing of a
+    /// typed record's coin, which the typed record replaces.
+    pub fn apply_precedence(&mut self, precedence: &RecordPrecedence) {
+        for (coin, values) in &mut self.records.addresses {
+            precedence.sort(values);
+            if let Some(best) = values.first() {
+                self.addresses.retain(|key, _| key == coin || !key.eq_ignore_ascii_case(coin));
+                self.addresses.insert(coin.clone(), best.value.clone());
+            }
+        }
+        for (key, values) in &mut self.records.text_records {
+            precedence.sort(values);
+            if let Some(best) = values.first() {
+                self.text_records.insert(key.clone(), best.value.clone());
+            }
+        }
+    }
+
+    /// Records whose sources disagree on the value
+    pub fn conflicts(&self) -> Vec<RecordConflict> {
+        let kinds = [
+            (RecordKind::Address, &self.records.addresses),
+            (RecordKind::Text, &self.records.text_records),
+        ];
+
+        let mut conflicts: Vec<RecordConflict> = kinds
+            .into_iter()
+            .flat_map(|(kind, records)| {
+                records
+                    .iter()
+                    .filter(|(_, values)| values.iter().any(|v| v.value != values[0].value))
+                    .map(move |(key, values)| RecordConflict {
+                        kind,
+                        key: key.clone(),
+                        values: values.clone(),
+                    })
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.key.cmp(&b.key));
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence_and_conflicts() {
+        let mut info = DomainInfo::test("ckelley.xrp", "rAlice");
+        info.add_address("BTC", Record::new("bc1-metadata", RecordSource::Metadata));
+        info.add_address("btc", Record::new("bc1-old", RecordSource::Memo).at(Some(10), None).by_owner(true));
+        info.add_address("btc", Record::new("bc1-new", RecordSource::Memo).at(Some(20), None).by_owner(true));
+        info.add_text_record("url", Record::new("https://a", RecordSource::Metadata));
+        info.add_text_record("url", Record::new("https://a", RecordSource::Service(NamingService::XRPDomains)));
+        info.text_records.insert("custom".to_string(), "kept".to_string());
+        info.addresses.insert("BTC".to_string(), "bc1-untyped".to_string());
+        info.addresses.insert("ETH".to_string(), "0xkept".to_string());
+
+        info.apply_precedence(&RecordPrecedence::default());
+        assert_eq!(info.addresses["btc"], "bc1-new");
+        assert!(!info.addresses.contains_key("BTC"));
+        assert_eq!(info.addresses["ETH"], "0xkept");
+        assert_eq!(info.text_records["custom"], "kept");
+        assert_eq!(info.records.text_records["url"][0].source.kind(), RecordSourceKind::Service);
+
+        let conflicts = info.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, RecordKind::Address);
+        assert_eq!(conflicts[0].values.len(), 3);
+
+        let metadata_first = RecordPrecedence {
+            sources: vec![RecordSourceKind::Metadata],
+            owner_first: false,
+        };
+        info.apply_precedence(&metadata_first);
+        assert_eq!(info.addresses["btc"], "bc1-metadata");
+    }
+}
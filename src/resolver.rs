@@ -1,280 +1,1227 @@
+use crate::cache::{
+    CacheCounters, CacheStats, CachedValue, ResolverCache, domain_key, holder_key, metadata_key,
+    owner_key,
+};
 use crate::client::XrplClient;
+use crate::config::{CacheConfig, MetadataPacing, ResolutionPolicy, ResolverConfig, XnsResolverBuilder};
+use crate::confusable::ConfusableScreen;
+use crate::did_document::DidDocument;
+use crate::did_storage::{DidRecords, DidStorage};
+use crate::domain::DomainName;
 use crate::error::{XnsError, XnsResult};
-use crate::models::{DomainInfo, NamingService, XrplNetwork};
-use crate::parser::{MetadataParser};
-use moka::future::Cache;
-use std::sync::Arc;
-use std::time::Duration;
+use crate::expiry::{ExpiryCheck, parse_expiry};
+use crate::issuers::Issuer;
+use crate::memo_storage::{
+    MemoStorage, SubdomainRecord, XNS_ADDRESSES_MEMO_TYPE, XNS_SUBDOMAINS_MEMO_TYPE,
+};
+use crate::models::{
+    BatchResolution, BatchStats, DomainInfo, NamingService, NftMetadata, OwnerStatus,
+    Registration, XrplNetwork, XrplNft,
+    unix_now,
+};
+use crate::address;
+use crate::owner;
+use crate::parser::MetadataParser;
+use crate::provider::{self, NameServiceProvider};
+use crate::records::{Record, RecordPrecedence, RecordSource};
+use crate::singleflight::SingleFlight;
+use crate::watch::{DomainWatch, WatchOptions};
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
-use tokio::time::sleep;
+use tokio::time::sleep_until;
+
+/// In-flight lookups, keyed by domain, address, NFTokenID or metadata URI
+#[derive(Default)]
+struct Flights {
+    resolve: SingleFlight<DomainInfo>,
+    reverse: SingleFlight<Vec<String>>,
+    owner: SingleFlight<(String, OwnerStatus)>,
+    metadata: SingleFlight<NftMetadata>,
+}
 
 /// XNS Resolver - main entry point for resolving .xrp domains
 #[derive(Clone)]
 pub struct XnsResolver {
     client: Arc<XrplClient>,
     parser: Arc<MetadataParser>,
-    cache: Cache<String, DomainInfo>,
+    /// Cache backend for domains, not-found results, metadata and owners
+    cache: Arc<dyn ResolverCache>,
+    /// TTLs for each kind of cache entry
+    cache_config: CacheConfig,
+    cache_counters: Arc<CacheCounters>,
+    /// Domains with a background refresh in flight
+    refreshing: Arc<Mutex<HashSet<String>>>,
+    /// Lookups currently running, shared by concurrent callers
+    flights: Arc<Flights>,
     network: XrplNetwork,
-    /// Rate limiter: max 10 concurrent metadata requests
+    /// Rate limiter shared by all lookups on this resolver
     metadata_semaphore: Arc<Semaphore>,
+    /// Max concurrent metadata requests per lookup
+    metadata_concurrency: usize,
+    /// Optional pacing between groups of metadata requests
+    metadata_pacing: Option<MetadataPacing>,
+    /// Naming service providers queried, in order
+    providers: Vec<Arc<dyn NameServiceProvider>>,
+    /// HTTP client shared with the metadata parser and providers
+    http: reqwest::Client,
+    /// Handling of names registered with several services
+    resolution: ResolutionPolicy,
+    /// Resolve domains whose owner cannot be determined
+    allow_unknown_owner: bool,
+    /// Homograph checks applied to every resolved name
+    confusables: Arc<ConfusableScreen>,
+    /// Expiry handling applied to every resolved name
+    expiry: ExpiryCheck,
+    /// Order in which record values from different sources are preferred
+    record_precedence: RecordPrecedence,
+    /// Read the owner's memo records for every resolved name
+    memo_records: bool,
+    /// Read the owner's DID records for every resolved name
+    did_records: bool,
 }
 
 impl XnsResolver {
     /// Create a new resolver for the given network
     pub async fn new(network: XrplNetwork) -> XnsResult<Self> {
-        let client = Arc::new(XrplClient::new(network));
-        let parser = Arc::new(MetadataParser::new());
-
-        // Cache with 5 min TTL and 1000 entry limit
-        let cache = Cache::builder()
-            .max_capacity(1000)
-            .time_to_live(Duration::from_secs(300))
-            .build();
-
-        Ok(Self {
-            client,
-            parser,
-            cache,
-            network,
-            metadata_semaphore: Arc::new(Semaphore::new(10)),
-        })
+        XnsResolverBuilder::from_config(ResolverConfig::new(network)).build().await
     }
 
     /// Create with custom RPC URL
     pub async fn with_url(network: XrplNetwork, rpc_url: String) -> XnsResult<Self> {
-        let client = Arc::new(XrplClient::with_url(network, rpc_url));
-        let parser = Arc::new(MetadataParser::new());
+        let config = ResolverConfig {
+            rpc_urls: vec![rpc_url],
+            ..ResolverConfig::new(network)
+        };
+        XnsResolverBuilder::from_config(config).build().await
+    }
+
+    /// Start building a resolver with custom configuration
+    pub fn builder() -> XnsResolverBuilder {
+        XnsResolverBuilder::new()
+    }
 
-        let cache = Cache::builder()
-            .max_capacity(1000)
-            .time_to_live(Duration::from_secs(300))
-            .build();
+    /// Create a resolver from a configuration without validating it
+    ///
+    /// `registered` providers replace the built-in provider of their
+    /// service; those whose service is not listed in the config are queried
+    /// last.
+    pub(crate) fn from_config(
+        config: ResolverConfig,
+        cache: Arc<dyn ResolverCache>,
+        mut registered: Vec<Arc<dyn NameServiceProvider>>,
+    ) -> XnsResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .connect_timeout(config.connect_timeout)
+            .user_agent(config.user_agent.clone())
+            .build()
+            .map_err(|e| XnsError::ConfigError(format!("Failed to build HTTP client: {}", e)))?;
+
+        let client = Arc::new(
+            XrplClient::with_endpoints(
+                config.network.clone(),
+                config.rpc_urls.clone(),
+                config.effective_clio_url().to_string(),
+                http.clone(),
+            )
+            .with_history_pages(config.history_pages),
+        );
+        let parser = Arc::new(MetadataParser::with_client(http.clone(), config.ipfs_gateways.clone()));
+
+        let mut providers = Vec::with_capacity(config.services.len() + registered.len());
+        for service in &config.services {
+            match registered.iter().position(|p| p.service() == *service) {
+                Some(idx) => providers.push(registered.remove(idx)),
+                None => providers.push(provider::builtin(
+                    service.clone(),
+                    config.issuers_for(service),
+                    config.tlds_for(service),
+                )),
+            }
+        }
+        providers.extend(registered);
+
+        let concurrency = config.metadata_concurrency.max(1);
 
         Ok(Self {
             client,
             parser,
             cache,
-            network,
-            metadata_semaphore: Arc::new(Semaphore::new(10)),
+            cache_config: config.cache.clone(),
+            cache_counters: Arc::new(CacheCounters::default()),
+            refreshing: Arc::new(Mutex::new(HashSet::new())),
+            flights: Arc::new(Flights::default()),
+            network: config.network,
+            metadata_semaphore: Arc::new(Semaphore::new(concurrency)),
+            metadata_concurrency: concurrency,
+            metadata_pacing: config.metadata_pacing,
+            providers,
+            http,
+            resolution: config.resolution,
+            allow_unknown_owner: config.allow_unknown_owner,
+            confusables: Arc::new(ConfusableScreen::new(&config.confusables)),
+            expiry: ExpiryCheck::new(&config.expiry),
+            record_precedence: config.record_precedence,
+            memo_records: config.memo_records,
+            did_records: config.did_records,
         })
     }
 
-    /// Resolve a .xrp domain to its owner and metadata
+    /// Get network type
+    pub fn network(&self) -> XrplNetwork {
+        self.network.clone()
+    }
+
+    /// HTTP client configured with the resolver's timeouts and user agent
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Resolve a domain to its owner and metadata
+    ///
+    /// The name is normalized first (see [`DomainName`]), so differently
+    /// cased or padded spellings share one cache entry. Names that look like
+    /// other names are flagged in `DomainInfo::warnings` or refused,
+    /// depending on the configured `ConfusableMode`. Expired names are
+    /// reported in `DomainInfo::expiry` or refused, depending on the
+    /// configured `ExpiryMode`.
     pub async fn resolve(&self, domain: &str) -> XnsResult<DomainInfo> {
-        // Validate domain format
-        if !domain.ends_with(".xrp") {
+        let name = self.parse_name(domain)?;
+        let info = self.resolve_name(&name).await?;
+        self.expiry.apply(self.confusables.apply(domain, &name, info)?)
+    }
+
+    /// Parse a name and check that some provider serves its TLD
+    pub(crate) fn parse_name(&self, domain: &str) -> XnsResult<DomainName> {
+        let name = DomainName::parse(domain)?;
+        if self.providers_for(&name).next().is_none() {
             return Err(XnsError::InvalidDomain(format!(
-                "Domain must end with .xrp: {}",
-                domain
+                "{}: no naming service resolves .{} names",
+                domain,
+                name.tld()
             )));
         }
+        Ok(name)
+    }
+
+    /// Resolve a normalized name through the domain cache
+    ///
+    /// Boxed because resolving a subdomain resolves its registered name
+    /// through here again.
+    pub(crate) fn resolve_name<'a>(&'a self, domain: &'a DomainName) -> BoxFuture<'a, XnsResult<DomainInfo>> {
+        async move {
+            // Check cache first
+            match self.cached_domain(domain).await {
+                Some(CachedValue::Domain(cached)) => {
+                    tracing::debug!("Cache hit for domain: {}", domain);
+                    return Ok(*cached);
+                }
+                Some(_) => {
+                    tracing::debug!("Negative cache hit for domain: {}", domain);
+                    return Err(XnsError::DomainNotFound(domain.to_string()));
+                }
+                None => {}
+            }
+
+            self.resolve_coalesced(domain).await
+        }
+        .boxed()
+    }
+
+    /// Resolve from the ledger and cache the outcome, sharing one lookup
+    /// between concurrent callers for the same domain
+    async fn resolve_coalesced(&self, domain: &DomainName) -> XnsResult<DomainInfo> {
+        let resolver = self.clone();
+        let key = domain.clone();
+
+        self.flights
+            .resolve
+            .run(domain.as_str(), move || async move {
+                let domain = key;
+                match resolver.resolve_uncached(&domain).await {
+                    Ok(domain_info) => {
+                        resolver.cache_domain(domain.as_str(), &domain_info).await;
+                        Ok(domain_info)
+                    }
+                    Err(XnsError::DomainNotFound(missing)) => {
+                        resolver.cache_not_found(domain.as_str()).await;
+                        Err(XnsError::DomainNotFound(missing))
+                    }
+                    Err(e) => Err(e),
+                }
+            })
+            .await
+    }
+
+    /// Look up a domain in the cache, recording hit/miss statistics
+    ///
+    /// Returns either `CachedValue::Domain` or `CachedValue::NotFound`. In
+    /// stale-while-revalidate mode a domain past its TTL is returned marked
+    /// stale and a background refresh is started.
+    async fn cached_domain(&self, domain: &DomainName) -> Option<CachedValue> {
+        match self.cache.get(&domain_key(domain.as_str())).await {
+            Some(CachedValue::Domain(mut info)) => {
+                let age = info
+                    .resolved_at
+                    .map(|resolved_at| unix_now().saturating_sub(resolved_at))
+                    .unwrap_or_default();
+
+                if !self.cache_config.stale_window.is_zero() && age >= self.cache_config.ttl.as_secs() {
+                    tracing::debug!("Serving stale {} (age {}s) while refreshing", domain, age);
+                    CacheCounters::record(&self.cache_counters.stale_hits);
+                    info.stale_age = Some(Duration::from_secs(age));
+                    self.spawn_refresh(domain);
+                } else {
+                    CacheCounters::record(&self.cache_counters.hits);
+                }
 
-        // Check cache first
-        if let Some(cached) = self.cache.get(domain).await {
-            tracing::debug!("Cache hit for domain: {}", domain);
-            return Ok(cached);
+                Some(CachedValue::Domain(info))
+            }
+            Some(CachedValue::NotFound) => {
+                CacheCounters::record(&self.cache_counters.negative_hits);
+                Some(CachedValue::NotFound)
+            }
+            _ => {
+                CacheCounters::record(&self.cache_counters.misses);
+                None
+            }
+        }
+    }
+
+    /// Refresh a stale domain in the background, at most once at a time
+    fn spawn_refresh(&self, domain: &DomainName) {
+        {
+            let mut refreshing = self.refreshing.lock().unwrap_or_else(|e| e.into_inner());
+            if !refreshing.insert(domain.to_string()) {
+                return;
+            }
+        }
+
+        let resolver = self.clone();
+        let domain = domain.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = resolver.resolve_coalesced(&domain).await
+                && !matches!(e, XnsError::DomainNotFound(_))
+            {
+                tracing::warn!("Background refresh of {} failed: {}", domain, e);
+            }
+
+            resolver
+                .refreshing
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(domain.as_str());
+        });
+    }
+
+    async fn cache_domain(&self, cache_key: &str, domain_info: &DomainInfo) {
+        // Keep entries past their TTL for the stale window
+        let ttl = self.cache_config.ttl + self.cache_config.stale_window;
+        let mut domain_info = domain_info.clone();
+        domain_info.stale_age = None;
+        domain_info.warnings.clear();
+
+        self.cache
+            .insert(&domain_key(cache_key), CachedValue::Domain(Box::new(domain_info)), ttl)
+            .await;
+    }
+
+    async fn cache_not_found(&self, cache_key: &str) {
+        self.cache
+            .insert(&domain_key(cache_key), CachedValue::NotFound, self.cache_config.negative_ttl)
+            .await;
+    }
+
+    /// Resolve a domain straight from the ledger, bypassing the domain caches
+    ///
+    /// Every provider that supports the name's TLD is asked in turn, and the
+    /// matches are settled by the resolution policy.
+    pub(crate) async fn resolve_uncached(&self, domain: &DomainName) -> XnsResult<DomainInfo> {
+        if domain.is_subdomain() {
+            return self.resolve_subdomain(domain).await;
         }
 
         tracing::info!("Resolving domain: {}", domain);
 
-        // Try each naming service
-        let services = [NamingService::XNS, NamingService::XRPDomains];
+        let first_only = self.resolution == ResolutionPolicy::Preferred;
+        let matches = self.registrations(domain, first_only).await?;
+        self.settle(domain, matches)
+    }
+
+    /// Resolve a domain with every naming service that knows it
+    ///
+    /// Registrations are returned in order of service preference, so callers
+    /// can warn about names registered with several services. Unlike
+    /// [`resolve`](Self::resolve), results are not cached. A subdomain has the
+    /// single registration of its parent.
+    pub async fn resolve_all_services(&self, domain: &str) -> XnsResult<Vec<DomainInfo>> {
+        let name = self.parse_name(domain)?;
+        if name.is_subdomain() {
+            return Ok(vec![self.resolve(domain).await?]);
+        }
+
+        self.registrations(&name, false)
+            .await?
+            .into_iter()
+            .map(|info| self.expiry.apply(self.confusables.apply(domain, &name, info)?))
+            .collect()
+    }
 
-        for service in &services {
-            match self.resolve_from_service(domain, *service).await {
+    /// Ask providers for a registered name, in order
+    ///
+    /// With `first_only`, the first match is returned and a transient error
+    /// from an earlier provider is skipped. Otherwise every provider must
+    /// answer, so that a failing service cannot hide a second registration.
+    /// Fails with `DomainNotFound` only when every provider answered
+    /// definitively; otherwise the last transient error is returned so that
+    /// it is not mistaken for (or cached as) an unregistered name.
+    /// Registrations refused as expired are skipped, failing with
+    /// `DomainExpired` when no other registration is found.
+    async fn registrations(&self, domain: &DomainName, first_only: bool) -> XnsResult<Vec<DomainInfo>> {
+        let mut matches = Vec::new();
+        let mut transient_error = None;
+        let mut expired = false;
+
+        for provider in self.providers_for(domain) {
+            let service = provider.service();
+            match provider.resolve(self, domain).await {
+                Ok(domain_info) if self.expiry.refuses(&domain_info) => {
+                    tracing::debug!("Service {} registration of {} has expired", service, domain);
+                    expired = true;
+                }
                 Ok(domain_info) => {
-                    // Cache the result
-                    self.cache.insert(domain.to_string(), domain_info.clone()).await;
-                    return Ok(domain_info);
+                    matches.push(self.enrich(provider.as_ref(), domain_info).await);
+                    if first_only {
+                        return Ok(matches);
+                    }
+                }
+                Err(e @ (XnsError::DomainNotFound(_) | XnsError::UnsupportedService(_))) => {
+                    tracing::debug!("Service {} failed for {}: {}", service, domain, e);
                 }
                 Err(e) => {
-                    tracing::debug!("Service {:?} failed for {}: {}", service, domain, e);
-                    continue;
+                    tracing::debug!("Service {} failed for {}: {}", service, domain, e);
+                    transient_error = Some(e);
                 }
             }
         }
 
-        Err(XnsError::DomainNotFound(domain.to_string()))
+        match transient_error {
+            Some(e) => Err(e),
+            None if matches.is_empty() && expired => Err(XnsError::DomainExpired(domain.to_string())),
+            None if matches.is_empty() => Err(XnsError::DomainNotFound(domain.to_string())),
+            None => Ok(matches),
+        }
     }
 
-    /// Resolve from a specific naming service
-    async fn resolve_from_service(
-        &self,
-        domain: &str,
-        service: NamingService,
-    ) -> XnsResult<DomainInfo> {
-        // Get issuer address for this service
-        let issuer = service
-            .issuer_address(self.network)
-            .ok_or_else(|| XnsError::UnsupportedService(format!("{:?}", service)))?;
+    /// Pick the registration to return from a name's matches, in order of
+    /// service preference
+    fn settle(&self, domain: &DomainName, mut matches: Vec<DomainInfo>) -> XnsResult<DomainInfo> {
+        if matches.is_empty() {
+            return Err(XnsError::DomainNotFound(domain.to_string()));
+        }
+
+        if self.resolution == ResolutionPolicy::RequireUnique && matches.len() > 1 {
+            let services: Vec<String> = matches.iter().map(|m| m.service.to_string()).collect();
+            return Err(XnsError::AmbiguousDomain(format!(
+                "{} is registered with {}",
+                domain,
+                services.join(", ")
+            )));
+        }
+
+        let mut preferred = matches.remove(0);
+        if self.resolution == ResolutionPolicy::ReturnAll {
+            preferred.other_registrations = matches.iter().map(Registration::from).collect();
+        }
+        self.expiry.apply(preferred)
+    }
+
+    /// Resolve a subdomain from its registered name's owner records
+    ///
+    /// The registered name is resolved (through the cache) first, then the
+    /// subdomain is looked up in its owner's DID, when DID records are
+    /// enabled, then in the latest `XNS_SUBDOMAINS` memo the owner sent.
+    /// Either must have been written since the owner acquired the registered
+    /// name.
+    async fn resolve_subdomain(&self, domain: &DomainName) -> XnsResult<DomainInfo> {
+        let registered = domain.registered();
+        tracing::info!("Resolving subdomain {} of {}", domain, registered);
 
-        tracing::debug!("Querying {:?} issuer: {}", service, issuer);
+        let parent = self.resolve_name(&registered).await?;
 
-        // OPTIMIZATION: Try Clio's nfts_by_issuer first (more efficient)
-        let nfts = match self.client.nfts_by_issuer(issuer, None).await {
-            Ok(nfts) => {
-                tracing::debug!("Using Clio nfts_by_issuer: found {} NFTs from {:?}", nfts.len(), service);
-                nfts
+        let owner = parent.known_owner().ok_or_else(|| {
+            XnsError::OwnerUnavailable(format!("owner of {} is unknown", registered))
+        })?;
+
+        // Keys are written by hand, so normalize them like any other name
+        let find = |records: HashMap<String, SubdomainRecord>| {
+            records
+                .into_iter()
+                .find(|(label, _)| {
+                    DomainName::parse(&format!("{}.{}", label, registered)).is_ok_and(|name| name == *domain)
+                })
+                .map(|(_, record)| record)
+        };
+
+        let mut found = None;
+        if self.did_records {
+            match DidStorage::new((*self.client).clone()).owner_records(owner, &parent.nft_id).await {
+                Ok(owner_did) => {
+                    if let Some(entry) = owner_did.entry
+                        && let Some(record) = find(entry.records.subdomains)
+                    {
+                        let source = Record::new(String::new(), RecordSource::Did).at(entry.ledger_index, None);
+                        found = Some((record, source, owner_did.acquired_ledger));
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to read DID of {}, using memo records: {}", owner, e),
             }
-            Err(e) => {
-                tracing::warn!("Clio nfts_by_issuer failed ({}), falling back to account_nfts", e);
-                // Fallback to account_nfts on issuer
-                self.client.account_nfts(issuer).await?
+        }
+
+        let (record, source, acquired_ledger) = match found {
+            Some(found) => found,
+            None => {
+                let owner_memo = MemoStorage::new((*self.client).clone())
+                    .owner_memo(owner, XNS_SUBDOMAINS_MEMO_TYPE, &parent.nft_id)
+                    .await?;
+                let Some(memo) = owner_memo.memo else {
+                    return Err(XnsError::DomainNotFound(domain.to_string()));
+                };
+                let record = find(MemoStorage::parse_subdomains(&memo.data)?)
+                    .ok_or_else(|| XnsError::DomainNotFound(domain.to_string()))?;
+                let source = Record::new(String::new(), RecordSource::Memo).at(memo.ledger_index, memo.timestamp);
+                (record, source, owner_memo.acquired_ledger)
             }
         };
 
-        tracing::debug!("Processing {} NFTs from {:?}", nfts.len(), service);
+        let owner = record.address.unwrap_or_else(|| parent.owner.clone());
+        let mut info = DomainInfo {
+            acquired_ledger,
+            expires_at: parent.expires_at,
+            expiry: parent.expiry,
+            metadata_uri: parent.metadata_uri,
+            parent: Some(registered.to_string()),
+            ..DomainInfo::new(domain.to_string(), owner, parent.owner_status, parent.nft_id, parent.service)
+        };
+        // Read from the owner's own DID or memos
+        let owner_record = |value| Record { value, ..source.clone() }.by_owner(true);
+        for (coin, address) in record.addresses {
+            info.add_address(&coin, owner_record(address));
+        }
+        for (key, value) in record.text_records {
+            info.add_text_record(&key, owner_record(value));
+        }
+        info.apply_precedence(&self.record_precedence);
+
+        Ok(info)
+    }
+
+    /// Providers that may know a name, in query order
+    fn providers_for<'a>(
+        &'a self,
+        domain: &'a DomainName,
+    ) -> impl Iterator<Item = &'a Arc<dyn NameServiceProvider>> + 'a {
+        self.providers.iter().filter(|p| p.supports_tld(domain.tld()))
+    }
+
+    /// Accounts of the issuers that could mint a name
+    pub(crate) fn issuer_accounts(&self, domain: &DomainName) -> Vec<String> {
+        self.providers_for(domain)
+            .flat_map(|provider| provider.issuers())
+            .map(|issuer| issuer.address.clone())
+            .collect()
+    }
+
+    /// Drop cached state that a ledger change may have made stale for a
+    /// resolved name: its NFT owner and metadata and, for a subdomain, its
+    /// registered name
+    pub(crate) async fn forget(&self, info: &DomainInfo) {
+        self.cache.invalidate(&owner_key(&info.nft_id)).await;
+        if let Some(uri) = &info.metadata_uri {
+            self.cache.invalidate(&metadata_key(uri)).await;
+        }
+        if let Some(parent) = &info.parent {
+            self.cache.invalidate(&domain_key(parent)).await;
+        }
+    }
+
+    /// Add a provider's off-ledger records, and the owner's memo and DID
+    /// records if enabled, to a resolved name
+    async fn enrich(&self, provider: &dyn NameServiceProvider, mut domain_info: DomainInfo) -> DomainInfo {
+        if let Err(e) = provider.enrich(self, &mut domain_info).await {
+            tracing::debug!("Enriching {} from {} failed: {}", domain_info.domain, provider.service(), e);
+        }
+        if self.memo_records
+            && let Err(e) = self.add_memo_records(&mut domain_info).await
+        {
+            tracing::debug!("Reading memo records of {} failed: {}", domain_info.domain, e);
+        }
+        if self.did_records
+            && let Err(e) = self.add_did_records(&mut domain_info).await
+        {
+            tracing::debug!("Reading DID records of {} failed: {}", domain_info.domain, e);
+        }
+
+        domain_info.apply_precedence(&self.record_precedence);
+        domain_info
+    }
+
+    /// Add the addresses in the owner's latest `XNS_ADDRESSES` memo, if it
+    /// was sent after the owner acquired the name
+    async fn add_memo_records(&self, domain_info: &mut DomainInfo) -> XnsResult<()> {
+        let Some(owner) = domain_info.known_owner() else {
+            return Ok(());
+        };
+        let owner_memo = MemoStorage::new((*self.client).clone())
+            .owner_memo(owner, XNS_ADDRESSES_MEMO_TYPE, &domain_info.nft_id)
+            .await?;
+        domain_info.acquired_ledger = owner_memo.acquired_ledger;
+        let Some(memo) = owner_memo.memo else {
+            return Ok(());
+        };
+
+        for (coin, address) in MemoStorage::parse_addresses(&memo.data)? {
+            let record = Record::new(address, RecordSource::Memo)
+                .at(memo.ledger_index, memo.timestamp)
+                .by_owner(true);
+            domain_info.add_address(&coin, record);
+        }
+        Ok(())
+    }
+
+    /// Add the addresses in the owner's DID, if it was set after the owner
+    /// acquired the name
+    async fn add_did_records(&self, domain_info: &mut DomainInfo) -> XnsResult<()> {
+        let Some(owner) = domain_info.known_owner() else {
+            return Ok(());
+        };
+        let owner_did = DidStorage::new((*self.client).clone())
+            .owner_records(owner, &domain_info.nft_id)
+            .await?;
+        if owner_did.acquired_ledger.is_some() {
+            domain_info.acquired_ledger = owner_did.acquired_ledger;
+        }
+        let Some(entry) = owner_did.entry else {
+            return Ok(());
+        };
+
+        for (coin, address) in entry.records.addresses {
+            let record = Record::new(address, RecordSource::Did)
+                .at(entry.ledger_index, None)
+                .by_owner(true);
+            domain_info.add_address(&coin, record);
+        }
+        Ok(())
+    }
 
-        // OPTIMIZATION: Search for matching domain with throttling and early exit
-        let target_domain_lower = domain.to_lowercase();
+    /// Resolve a name minted as an NFT by one of `issuers`
+    ///
+    /// This is the default [`NameServiceProvider::resolve`]: the issuers'
+    /// NFTs are scanned for metadata naming `domain`, and the owner of the
+    /// matching NFT is looked up. Fails with `UnsupportedService` when
+    /// `issuers` is empty.
+    pub async fn resolve_issued(
+        &self,
+        service: NamingService,
+        issuers: &[Issuer],
+        domain: &DomainName,
+    ) -> XnsResult<DomainInfo> {
+        let nfts = self.issuer_nfts(&service, issuers).await?;
 
-        for (idx, nft) in nfts.iter().enumerate() {
-            if let Some(uri_hex) = &nft.uri {
-                // Acquire semaphore permit for rate limiting
-                let _permit = self.metadata_semaphore.acquire().await
-                    .map_err(|e| XnsError::InternalError(format!("Semaphore error: {}", e)))?;
+        tracing::debug!("Processing {} NFTs from {}", nfts.len(), service);
 
-                // Small delay to avoid overwhelming the metadata server
-                if idx > 0 && idx % 10 == 0 {
-                    sleep(Duration::from_millis(100)).await;
-                }
+        // OPTIMIZATION: Fetch metadata concurrently and stop at the first match
+        let candidates: Vec<&XrplNft> = nfts.iter().filter(|nft| nft.uri.is_some()).collect();
+        let mut fetches = self.fetch_metadata(candidates);
+        let mut checked = 0;
 
-                match self.parser.parse_uri(uri_hex).await {
-                    Ok(metadata) => {
-                        if let Some(nft_domain) = MetadataParser::extract_domain_name(&metadata) {
-                            if nft_domain.eq_ignore_ascii_case(&target_domain_lower) {
-                                tracing::info!("✓ Found domain {} in NFT {} (checked {} NFTs)",
-                                    domain, nft.nft_token_id, idx + 1);
-
-                                // Get the actual owner (might not be issuer)
-                                let owner = self.get_nft_owner(&nft.nft_token_id).await?;
-
-                                let mut domain_info = DomainInfo {
-                                    domain: nft_domain,
-                                    owner,
-                                    nft_id: nft.nft_token_id.clone(),
-                                    service,
-                                    addresses: Default::default(),
-                                    text_records: Default::default(),
-                                    expires_at: None, // TODO: Parse expiration from metadata
-                                    metadata: Some(metadata),
-                                };
-
-                                // Enhance with API data if available
-                                let _ = self.enhance_with_xrp_domains_api(&mut domain_info).await;
-
-                                return Ok(domain_info);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        tracing::debug!("Skipping NFT {}: {}", nft.nft_token_id, e);
+        while let Some((nft, fetched)) = fetches.next().await {
+            checked += 1;
+
+            match fetched {
+                Ok(metadata) => {
+                    let Some(nft_domain) = MetadataParser::extract_domain_name(&metadata) else {
                         continue;
+                    };
+                    self.confusables.index(&nft_domain);
+
+                    if nft_domain == *domain {
+                        tracing::info!("✓ Found domain {} in NFT {} (checked {} NFTs)",
+                            domain, nft.nft_token_id, checked);
+
+                        // Cancel outstanding metadata requests
+                        drop(fetches);
+
+                        return self
+                            .build_domain_info(nft_domain, nft, service, metadata)
+                            .await;
                     }
                 }
+                Err(e) => {
+                    tracing::debug!("Skipping NFT {}: {}", nft.nft_token_id, e);
+                }
             }
         }
 
-        tracing::warn!("Domain {} not found after checking {} NFTs from {:?}",
+        tracing::warn!("Domain {} not found after checking {} NFTs from {}",
             domain, nfts.len(), service);
 
         Err(XnsError::DomainNotFound(domain.to_string()))
     }
 
-    /// Get current owner of an NFT via Clio
-    async fn get_nft_owner(&self, nft_id: &str) -> XnsResult<String> {
-        match self.client.nft_info(nft_id).await {
-            Ok(nft_info) => {
-                if nft_info.is_burned {
-                    return Err(XnsError::DomainNotFound(
-                        "NFT has been burned".to_string(),
-                    ));
+    /// List every NFT minted by a naming service's issuers
+    async fn issuer_nfts(&self, service: &NamingService, issuers: &[Issuer]) -> XnsResult<Vec<XrplNft>> {
+        if issuers.is_empty() {
+            return Err(XnsError::UnsupportedService(service.to_string()));
+        }
+
+        let mut nfts = Vec::new();
+
+        for issuer in issuers {
+            tracing::debug!("Querying {} issuer: {}", service, issuer.address);
+
+            // OPTIMIZATION: Try Clio's nfts_by_issuer first (more efficient)
+            let issued = match self.client.nfts_by_issuer(&issuer.address, issuer.taxon, None).await {
+                Ok(issued) => {
+                    tracing::debug!("Using Clio nfts_by_issuer: found {} NFTs from {}", issued.len(), service);
+                    issued
                 }
-                Ok(nft_info.owner)
-            }
-            Err(e) => {
-                tracing::warn!("Failed to get NFT owner via Clio: {}", e);
-                // Fallback: return placeholder
-                Ok("rUnknownOwner".to_string())
+                Err(e) => {
+                    tracing::warn!("Clio nfts_by_issuer failed ({}), falling back to account_nfts", e);
+                    // Fallback to account_nfts on issuer
+                    self.client.account_nfts(&issuer.address).await?
+                }
+            };
+            nfts.extend(issued.into_iter().filter(|nft| issuer.matches(nft)));
+        }
+
+        Ok(nfts)
+    }
+
+    /// Fetch metadata for many NFTs concurrently
+    ///
+    /// Requests are bounded by the metadata concurrency limit and pacing.
+    /// Results arrive in completion order; dropping the stream cancels any
+    /// requests still in flight.
+    fn fetch_metadata<'a>(
+        &'a self,
+        nfts: Vec<&'a XrplNft>,
+    ) -> impl Stream<Item = (&'a XrplNft, XnsResult<NftMetadata>)> + 'a {
+        let started = tokio::time::Instant::now();
+        let pacing = self.metadata_pacing;
+
+        stream::iter(nfts.into_iter().enumerate())
+            .map(move |(idx, nft)| async move {
+                // Small delay to avoid overwhelming the metadata server
+                if let Some(pacing) = pacing {
+                    let window = (idx / pacing.every.max(1)) as u32;
+                    sleep_until(started + pacing.delay * window).await;
+                }
+
+                let uri_hex = nft.uri.as_deref().unwrap_or_default();
+                (nft, self.metadata(uri_hex).await)
+            })
+            .buffer_unordered(self.metadata_concurrency)
+    }
+
+    /// Fetch NFT metadata through the metadata cache
+    async fn metadata(&self, uri_hex: &str) -> XnsResult<NftMetadata> {
+        if let Some(CachedValue::Metadata(metadata)) = self.cache.get(&metadata_key(uri_hex)).await {
+            CacheCounters::record(&self.cache_counters.metadata_hits);
+            return Ok(metadata);
+        }
+        CacheCounters::record(&self.cache_counters.metadata_misses);
+
+        // Held by each waiter rather than the shared flight, so it is
+        // released as soon as the caller goes away
+        let _permit = self.metadata_semaphore.acquire().await
+            .map_err(|e| XnsError::InternalError(format!("Semaphore error: {}", e)))?;
+        let resolver = self.clone();
+        let uri = uri_hex.to_string();
+
+        self.flights
+            .metadata
+            .run(uri_hex, move || async move {
+                let metadata = resolver.parser.parse_uri(&uri).await?;
+                resolver
+                    .cache
+                    .insert(
+                        &metadata_key(&uri),
+                        CachedValue::Metadata(metadata.clone()),
+                        resolver.cache_config.metadata_ttl,
+                    )
+                    .await;
+
+                Ok(metadata)
+            })
+            .await
+    }
+
+    /// Build a `DomainInfo` for a matched domain NFT
+    async fn build_domain_info(
+        &self,
+        domain: DomainName,
+        nft: &XrplNft,
+        service: NamingService,
+        metadata: NftMetadata,
+    ) -> XnsResult<DomainInfo> {
+        // Get the actual owner (might not be issuer)
+        let (owner, owner_status) = match self.get_nft_owner(nft).await {
+            Ok(found) => found,
+            Err(XnsError::OwnerUnavailable(reason)) if self.allow_unknown_owner => {
+                tracing::warn!("Owner of {} unknown: {}", domain, reason);
+                (String::new(), OwnerStatus::Unknown)
             }
+            Err(e) => return Err(e),
+        };
+
+        let records = MetadataParser::extract_records(&metadata);
+        let mut domain_info = DomainInfo {
+            expires_at: parse_expiry(&metadata),
+            metadata: Some(metadata),
+            metadata_uri: nft.uri.clone(),
+            ..DomainInfo::new(domain.to_string(), owner, owner_status, nft.nft_token_id.clone(), service)
+        };
+        for (coin, address) in records.addresses {
+            domain_info.add_address(&coin, Record::new(address, RecordSource::Metadata));
         }
+        for (key, value) in records.text_records {
+            domain_info.add_text_record(&key, Record::new(value, RecordSource::Metadata));
+        }
+        domain_info.apply_precedence(&self.record_precedence);
+
+        Ok(domain_info)
     }
 
-    /// Fetch additional data from XRP Domains API if available
-    async fn enhance_with_xrp_domains_api(&self, domain_info: &mut DomainInfo) -> XnsResult<()> {
-        if domain_info.service != NamingService::XRPDomains {
-            return Ok(());
+    /// Resolve many domains at once, sharing work between them
+    ///
+    /// Each naming service's issuer is listed once for the whole batch and
+    /// every distinct NFT URI is fetched at most once, bounded by the
+    /// metadata concurrency limit and pacing. Providers without issuers
+    /// resolve the remaining names one by one. Duplicate names in the input
+    /// are resolved once. Results are returned in input order.
+    pub async fn resolve_many(&self, domains: &[&str]) -> BatchResolution {
+        let started = Instant::now();
+        let mut stats = BatchStats {
+            total: domains.len(),
+            ..Default::default()
+        };
+
+        let mut results: Vec<Option<XnsResult<DomainInfo>>> = domains.iter().map(|_| None).collect();
+        let mut pending: HashMap<DomainName, Vec<usize>> = HashMap::new();
+        let mut names: Vec<Option<DomainName>> = domains.iter().map(|_| None).collect();
+        // Resolved after their registered names, which the batch may cache
+        let mut subdomains: HashMap<DomainName, Vec<usize>> = HashMap::new();
+
+        for (idx, domain) in domains.iter().enumerate() {
+            match self.parse_name(domain) {
+                Ok(name) if name.is_subdomain() => {
+                    subdomains.entry(name.clone()).or_default().push(idx);
+                    names[idx] = Some(name);
+                }
+                Ok(name) => {
+                    pending.entry(name.clone()).or_default().push(idx);
+                    names[idx] = Some(name);
+                }
+                Err(e) => results[idx] = Some(Err(e)),
+            }
         }
+        stats.unique = pending.len() + subdomains.len();
 
-        let api_url = format!(
-            "https://app.xrpdomains.xyz/api/xrplnft/getAddress?domain={}",
-            domain_info.domain
-        );
+        // Serve what we can from the cache
+        let keys: Vec<DomainName> = pending.keys().cloned().collect();
+        for key in keys {
+            match self.cached_domain(&key).await {
+                Some(CachedValue::Domain(cached)) => {
+                    stats.cache_hits += 1;
+                    for idx in pending.remove(&key).unwrap_or_default() {
+                        results[idx] = Some(Ok((*cached).clone()));
+                    }
+                }
+                Some(_) => {
+                    stats.cache_hits += 1;
+                    for idx in pending.remove(&key).unwrap_or_default() {
+                        results[idx] = Some(Err(XnsError::DomainNotFound(domains[idx].to_string())));
+                    }
+                }
+                None => {}
+            }
+        }
+
+        // Names still to look up; a name leaves once found, unless every
+        // service must be asked
+        let first_only = self.resolution == ResolutionPolicy::Preferred;
+        let indices_of = pending;
+        let mut pending: HashSet<DomainName> = indices_of.keys().cloned().collect();
+        let mut found: HashMap<DomainName, Vec<DomainInfo>> = HashMap::new();
+        let mut failures: HashMap<DomainName, XnsError> = HashMap::new();
+        // Names whose only registrations so far were refused as expired
+        let mut expired: HashSet<DomainName> = HashSet::new();
+        let mut listing_error = None;
+
+        for provider in &self.providers {
+            if pending.is_empty() {
+                break;
+            }
+            let service = provider.service();
+            let outcomes: Vec<(DomainName, XnsResult<DomainInfo>)> = if provider.issuers().is_empty() {
+                // Resolved one name at a time
+                let names: Vec<DomainName> = pending
+                    .iter()
+                    .filter(|name| provider.supports_tld(name.tld()))
+                    .cloned()
+                    .collect();
+                stream::iter(names)
+                    .map(|name| async move {
+                        let outcome = provider.resolve(self, &name).await;
+                        (name, outcome)
+                    })
+                    .buffer_unordered(self.metadata_concurrency)
+                    .collect()
+                    .await
+            } else {
+                let nfts = match self.issuer_nfts(&service, provider.issuers()).await {
+                    Ok(nfts) => nfts,
+                    Err(XnsError::UnsupportedService(_)) => continue,
+                    Err(e) => {
+                        tracing::debug!("Service {} failed for batch: {}", service, e);
+                        listing_error = Some(e);
+                        continue;
+                    }
+                };
+                stats.issuers_listed += 1;
+                stats.nfts_scanned += nfts.len();
+
+                // Fetch each distinct URI once
+                let mut seen_uris = HashSet::new();
+                let candidates: Vec<&XrplNft> = nfts
+                    .iter()
+                    .filter(|nft| nft.uri.as_ref().is_some_and(|uri| seen_uris.insert(uri.clone())))
+                    .collect();
+
+                let mut fetches = self.fetch_metadata(candidates);
 
-        tracing::debug!("Fetching XRP Domains API data for {}", domain_info.domain);
-
-        match self.client.client.get(&api_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    if let Ok(api_data) = response.json::<serde_json::Value>().await {
-                        if let Some(data) = api_data.get("data") {
-                            // Extract addresses
-                            if let Some(addresses) = data.get("addresses").and_then(|a| a.as_array()) {
-                                for addr in addresses {
-                                    if let (Some(symbol), Some(address)) = (
-                                        addr.get("symbol").and_then(|s| s.as_str()),
-                                        addr.get("address").and_then(|a| a.as_str()),
-                                    ) {
-                                        domain_info.addresses.insert(
-                                            symbol.to_lowercase(),
-                                            address.to_string(),
-                                        );
-                                    }
-                                }
-                            }
-
-                            // Extract profile text records
-                            if let Some(profile) = data.get("profile_info") {
-                                if let Some(email) = profile.get("email").and_then(|e| e.as_str()) {
-                                    domain_info.text_records.insert("email".to_string(), email.to_string());
-                                }
-                                if let Some(twitter) = profile.get("twitter").and_then(|t| t.as_str()) {
-                                    domain_info.text_records.insert("twitter".to_string(), twitter.to_string());
-                                }
-                                if let Some(github) = profile.get("github").and_then(|g| g.as_str()) {
-                                    domain_info.text_records.insert("github".to_string(), github.to_string());
-                                }
-                                if let Some(website) = profile.get("website").and_then(|w| w.as_str()) {
-                                    domain_info.text_records.insert("website".to_string(), website.to_string());
-                                }
-                            }
-
-                            tracing::info!("Enhanced {} with XRP Domains API data", domain_info.domain);
+                let mut matches = Vec::new();
+                let mut matched = HashSet::new();
+                while let Some((nft, fetched)) = fetches.next().await {
+                    stats.metadata_fetched += 1;
+
+                    let Ok(metadata) = fetched else {
+                        continue;
+                    };
+                    let Some(nft_domain) = MetadataParser::extract_domain_name(&metadata) else {
+                        continue;
+                    };
+                    self.confusables.index(&nft_domain);
+                    if !provider.supports_tld(nft_domain.tld()) {
+                        continue;
+                    }
+
+                    if pending.contains(&nft_domain) && matched.insert(nft_domain.clone()) {
+                        matches.push((nft, nft_domain, metadata));
+                        if matched.len() == pending.len() {
+                            break;
+                        }
+                    }
+                }
+                // Stop any metadata requests still in flight
+                drop(fetches);
+
+                stream::iter(matches)
+                    .map(|(nft, nft_domain, metadata)| {
+                        let service = service.clone();
+                        async move {
+                            let outcome = self
+                                .build_domain_info(nft_domain.clone(), nft, service, metadata)
+                                .await;
+                            (nft_domain, outcome)
+                        }
+                    })
+                    .buffer_unordered(self.metadata_concurrency)
+                    .collect()
+                    .await
+            };
+
+            for (name, outcome) in outcomes {
+                match outcome {
+                    Ok(domain_info) if self.expiry.refuses(&domain_info) => {
+                        expired.insert(name);
+                    }
+                    Ok(domain_info) => {
+                        let domain_info = self.enrich(provider.as_ref(), domain_info).await;
+                        found.entry(name.clone()).or_default().push(domain_info);
+                        if first_only {
+                            pending.remove(&name);
                         }
                     }
+                    Err(XnsError::DomainNotFound(_) | XnsError::UnsupportedService(_)) => {}
+                    Err(e) => {
+                        tracing::debug!("Service {} failed for {}: {}", service, name, e);
+                        // A matched NFT that cannot be read settles the
+                        // name, as it does for `resolve`
+                        if first_only && !provider.issuers().is_empty() {
+                            pending.remove(&name);
+                        }
+                        failures.insert(name, e);
+                    }
+                }
+            }
+        }
+
+        for (name, indices) in indices_of {
+            let matches = found.remove(&name).unwrap_or_default();
+            let failure = failures.remove(&name).or_else(|| {
+                // Another listing may have held a second registration
+                (!first_only && !matches.is_empty()).then(|| listing_error.clone()).flatten()
+            });
+
+            let outcome = match failure {
+                Some(_) if first_only && !matches.is_empty() => self.settle(&name, matches),
+                Some(e) => Err(e),
+                None if matches.is_empty() && expired.contains(&name) => {
+                    Err(XnsError::DomainExpired(name.to_string()))
+                }
+                None if matches.is_empty() => {
+                    // Only remember misses when every issuer was actually listed
+                    if listing_error.is_none() {
+                        self.cache_not_found(name.as_str()).await;
+                    }
+                    Err(XnsError::DomainNotFound(name.to_string()))
+                }
+                None => self.settle(&name, matches),
+            };
+
+            if let Ok(domain_info) = &outcome {
+                self.cache_domain(&domain_info.domain, domain_info).await;
+            }
+            for idx in indices {
+                results[idx] = Some(match &outcome {
+                    Err(XnsError::DomainNotFound(_)) => {
+                        Err(XnsError::DomainNotFound(domains[idx].to_string()))
+                    }
+                    outcome => outcome.clone(),
+                });
+            }
+        }
+
+        let resolved_subdomains: Vec<_> = stream::iter(subdomains)
+            .map(|(name, indices)| async move { (indices, self.resolve_name(&name).await) })
+            .buffer_unordered(self.metadata_concurrency)
+            .collect()
+            .await;
+        for (indices, outcome) in resolved_subdomains {
+            for idx in indices {
+                results[idx] = Some(outcome.clone());
+            }
+        }
+
+        let results: Vec<XnsResult<DomainInfo>> = results
+            .into_iter()
+            .zip(names)
+            .zip(domains)
+            .map(|((result, name), input)| {
+                let result = result.unwrap_or_else(|| {
+                    Err(XnsError::InternalError("Batch entry left unresolved".to_string()))
+                });
+                match (result, name) {
+                    (Ok(info), Some(name)) => self
+                        .confusables
+                        .apply(input, &name, info)
+                        .and_then(|info| self.expiry.apply(info)),
+                    (result, _) => result,
                 }
-                Ok(())
+            })
+            .collect();
+
+        for result in &results {
+            match result {
+                Ok(_) => stats.resolved += 1,
+                Err(XnsError::DomainNotFound(_)) => stats.not_found += 1,
+                Err(XnsError::DomainExpired(_)) => stats.expired += 1,
+                Err(_) => stats.failed += 1,
+            }
+        }
+        stats.elapsed = started.elapsed();
+
+        tracing::info!(
+            "Batch resolved {}/{} domains ({} unique, {} cache hits) in {:?}",
+            stats.resolved, stats.total, stats.unique, stats.cache_hits, stats.elapsed
+        );
+
+        BatchResolution { results, stats }
+    }
+
+    /// Get the current owner of an NFT
+    ///
+    /// Clio's `nft_info` is tried first. Without Clio the owner is
+    /// reconstructed from rippled alone (see the `owner` module), starting
+    /// from the account the NFT was listed under, the last holder seen for
+    /// this NFT and finally its issuer. Fails with `OwnerUnavailable` when every strategy
+    /// fails; a placeholder address is never returned.
+    async fn get_nft_owner(&self, nft: &XrplNft) -> XnsResult<(String, OwnerStatus)> {
+        let nft_id = nft.nft_token_id.as_str();
+
+        if let Some(CachedValue::Owner { owner, status }) = self.cache.get(&owner_key(nft_id)).await {
+            CacheCounters::record(&self.cache_counters.owner_hits);
+            return Ok((owner, status));
+        }
+        CacheCounters::record(&self.cache_counters.owner_misses);
+
+        let resolver = self.clone();
+        let nft = nft.clone();
+
+        self.flights
+            .owner
+            .run(nft_id, move || async move {
+                let nft_id = nft.nft_token_id.as_str();
+                let (owner, status) = resolver.lookup_nft_owner(&nft).await?;
+                let cached = CachedValue::Owner { owner: owner.clone(), status };
+
+                // The last holder outlives the owner entry as a starting point
+                // for tracing transfers on the ledger
+                resolver
+                    .cache
+                    .insert(&holder_key(nft_id), cached.clone(), resolver.cache_config.metadata_ttl)
+                    .await;
+                resolver
+                    .cache
+                    .insert(&owner_key(nft_id), cached, resolver.cache_config.owner_ttl)
+                    .await;
+                Ok((owner, status))
+            })
+            .await
+    }
+
+    async fn lookup_nft_owner(&self, nft: &XrplNft) -> XnsResult<(String, OwnerStatus)> {
+        let nft_id = nft.nft_token_id.as_str();
+
+        let mut last_error = match self.client.nft_info(nft_id).await {
+            Ok(nft_info) if nft_info.is_burned => {
+                return Err(XnsError::DomainNotFound(format!("NFT {} has been burned", nft_id)));
             }
+            Ok(nft_info) => return Ok((nft_info.owner, OwnerStatus::Clio)),
             Err(e) => {
-                tracing::debug!("XRP Domains API failed: {}", e);
-                Ok(()) // Don't fail if API is unavailable
+                tracing::warn!("Failed to get NFT owner via Clio, tracing on rippled: {}", e);
+                e
+            }
+        };
+
+        let last_holder = match self.cache.get(&holder_key(nft_id)).await {
+            Some(CachedValue::Owner { owner, .. }) => Some(owner),
+            _ => None,
+        };
+        let issuer = nft.issuer.clone().or_else(|| address::nft_issuer(nft_id, self.network.address_prefix()));
+
+        let mut starts: Vec<String> = Vec::new();
+        for start in [nft.owner.clone(), last_holder, issuer].into_iter().flatten() {
+            if !starts.contains(&start) {
+                starts.push(start);
             }
         }
+
+        for start in &starts {
+            match owner::trace_owner(&self.client, start, nft_id).await {
+                Ok(Some(owner)) => return Ok((owner, OwnerStatus::Ledger)),
+                Ok(None) => tracing::debug!("No holder of {} found from {}", nft_id, start),
+                Err(e @ XnsError::DomainNotFound(_)) => return Err(e),
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(XnsError::OwnerUnavailable(format!("{}: {}", nft_id, last_error)))
     }
 
     /// Reverse lookup: find domains owned by an address
+    ///
+    /// Concurrent lookups for the same address share one ledger query.
     pub async fn reverse_lookup(&self, address: &str) -> XnsResult<Vec<String>> {
+        let resolver = self.clone();
+        let owned_address = address.to_string();
+
+        self.flights
+            .reverse
+            .run(address, move || async move {
+                resolver.reverse_lookup_uncached(&owned_address).await
+            })
+            .await
+    }
+
+    /// The primary name of an address, confirmed by resolving it back
+    ///
+    /// The account chooses its primary name with an `XNS_PRIMARY_NAME`
+    /// record in its DID, when DID records are enabled, or else in its
+    /// latest memo. Since any account can claim any name, the name is only
+    /// returned when a fresh lookup, bypassing the domain and owner caches,
+    /// resolves it to `address`; otherwise, or when no name was chosen, the
+    /// result is `None`.
+    pub async fn primary_name(&self, address: &str) -> XnsResult<Option<String>> {
+        let mut claimed = None;
+        if self.did_records {
+            match DidStorage::new((*self.client).clone()).get_records(address).await {
+                Ok(entry) => claimed = entry.and_then(|entry| entry.records.primary_name),
+                Err(e) => tracing::warn!("Failed to read DID of {}, using memo records: {}", address, e),
+            }
+        }
+        let claimed = match claimed {
+            Some(name) => Some(name),
+            None => MemoStorage::new((*self.client).clone()).get_primary_name(address).await?,
+        };
+        let Some(claimed) = claimed else {
+            return Ok(None);
+        };
+
+        // The lookup may have read the owner or registered name from the
+        // cache; drop them and look again before confirming, as they may
+        // predate a transfer
+        let confirmed = match self.resolve_fresh(&claimed).await {
+            Ok(info) if info.known_owner() == Some(address) => {
+                self.forget(&info).await;
+                self.resolve_fresh(&claimed).await
+            }
+            outcome => outcome,
+        };
+
+        // Confusable, expired or ambiguous names are not confirmed either
+        match confirmed {
+            Ok(info) if info.known_owner() == Some(address) => Ok(Some(info.domain)),
+            Ok(info) => {
+                tracing::debug!("Primary name {} of {} resolves to {}", claimed, address, info.owner);
+                Ok(None)
+            }
+            Err(
+                XnsError::DomainNotFound(_)
+                | XnsError::InvalidDomain(_)
+                | XnsError::DomainExpired(_)
+                | XnsError::ConfusableDomain(_)
+                | XnsError::AmbiguousDomain(_),
+            ) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Resolve a domain like [`Self::resolve`], but without the domain cache
+    async fn resolve_fresh(&self, domain: &str) -> XnsResult<DomainInfo> {
+        let name = self.parse_name(domain)?;
+        let info = self.resolve_uncached(&name).await?;
+        self.expiry.apply(self.confusables.apply(domain, &name, info)?)
+    }
+
+    async fn reverse_lookup_uncached(&self, address: &str) -> XnsResult<Vec<String>> {
         tracing::info!("Reverse lookup for address: {}", address);
 
-        let nfts = self.client.account_nfts(address).await?;
-        let mut domains = Vec::new();
+        let mut domains: Vec<String> = Vec::new();
 
-        for nft in nfts {
-            if let Some(uri_hex) = &nft.uri {
-                if let Ok(metadata) = self.parser.parse_uri(uri_hex).await {
-                    if let Some(domain) = MetadataParser::extract_domain_name(&metadata) {
-                        domains.push(domain);
-                    }
+        for provider in &self.providers {
+            for name in provider.reverse(self, address).await? {
+                let name = name.to_string();
+                if !domains.contains(&name) {
+                    domains.push(name);
                 }
             }
         }
@@ -282,9 +1229,56 @@ impl XnsResolver {
         Ok(domains)
     }
 
+    /// Names held by `address` on NFTs minted by one of `issuers`, under
+    /// the issuer's taxon if it has one
+    ///
+    /// This is the default [`NameServiceProvider::reverse`]. NFTs from other
+    /// issuers are ignored, so a look-alike NFT cannot claim a name.
+    pub async fn names_held_by(&self, address: &str, issuers: &[Issuer]) -> XnsResult<Vec<DomainName>> {
+        if issuers.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let nfts = self.client.account_nfts(address).await?;
+        let mut names = Vec::new();
+
+        for nft in nfts {
+            if !issuers.iter().any(|issuer| issuer.matches(&nft)) {
+                continue;
+            }
+
+            if let Some(uri_hex) = &nft.uri
+                && let Ok(metadata) = self.metadata(uri_hex).await
+                && let Some(domain) = MetadataParser::extract_domain_name(&metadata)
+            {
+                names.push(domain);
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// Watch a domain for ownership and record changes
+    ///
+    /// Polls the ledger every 60 seconds. The first event is the domain's
+    /// current state; afterwards only changes are emitted.
+    pub fn watch(&self, domain: &str) -> DomainWatch {
+        self.watch_with(domain, WatchOptions::default())
+    }
+
+    /// Watch a domain with custom polling or ledger subscription options
+    pub fn watch_with(&self, domain: &str, options: WatchOptions) -> DomainWatch {
+        crate::watch::spawn(self.clone(), domain.to_string(), options)
+    }
+
     /// Clear the cache
     pub async fn clear_cache(&self) {
-        self.cache.invalidate_all();
+        self.cache.invalidate_all().await;
+    }
+
+    /// Cache hit/miss counters since the resolver was created
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_counters.snapshot()
     }
 
     /// Build an unsigned transaction for storing blockchain addresses in XRPL memos
@@ -329,13 +1323,66 @@ impl XnsResolver {
         memo_storage.build_storage_transaction(account, addresses)
     }
 
+    /// Build an unsigned transaction publishing the subdomains of names owned
+    /// by `account`
+    ///
+    /// Keys are subdomain labels relative to the registered name (`pay` for
+    /// `pay.ckelley.xrp`). The memo replaces all previously published
+    /// subdomains of the account.
+    pub fn build_subdomain_storage_tx(
+        &self,
+        account: &str,
+        records: std::collections::HashMap<String, SubdomainRecord>,
+    ) -> XnsResult<String> {
+        MemoStorage::new((*self.client).clone()).build_subdomain_transaction(account, records)
+    }
+
+    /// Resolve a name and describe it as a W3C DID document
+    ///
+    /// The document's subject is the owner's `did:xrpl` identifier; see
+    /// [`DidDocument`].
+    pub async fn did_document(&self, domain: &str) -> XnsResult<DidDocument> {
+        let info = self.resolve(domain).await?;
+        DidDocument::from_domain_info(&info, &self.network)
+    }
+
+    /// Build an unsigned transaction setting `account`'s primary name in a
+    /// memo
+    ///
+    /// The name only takes effect while it resolves to `account`.
+    pub fn build_primary_name_tx(&self, account: &str, name: &str) -> XnsResult<String> {
+        let name = self.parse_name(name)?;
+        MemoStorage::new((*self.client).clone()).build_primary_name_transaction(account, name.as_ref())
+    }
+
+    /// Build an unsigned DIDSet transaction storing name records in
+    /// `account`'s XLS-40 DID
+    ///
+    /// The DID's previous records are replaced.
+    pub fn build_did_records_tx(&self, account: &str, records: &DidRecords) -> XnsResult<String> {
+        DidStorage::new((*self.client).clone()).build_set_transaction(account, records)
+    }
+
+    /// Build an unsigned DIDDelete transaction removing `account`'s DID
+    pub fn build_did_delete_tx(&self, account: &str) -> XnsResult<String> {
+        DidStorage::new((*self.client).clone()).build_delete_transaction(account)
+    }
+
+    /// Get the name records stored in an account's DID
+    ///
+    /// Empty when the account has no DID or its fields hold no records.
+    pub async fn get_did_records(&self, account: &str) -> XnsResult<DidRecords> {
+        Ok(DidStorage::new((*self.client).clone())
+            .get_records(account)
+            .await?
+            .map(|entry| entry.records)
+            .unwrap_or_default())
+    }
+
     /// Get addresses stored in XRPL memos for an account
     ///
     /// This queries the account's transaction history to find the latest
     /// XNS_ADDRESSES memo and returns the stored blockchain addresses.
-    ///
-    /// Note: This is a prototype implementation. Full functionality requires
-    /// additional RPC methods.
     pub async fn get_memo_addresses(
         &self,
         account: &str,
@@ -351,6 +1398,12 @@ impl XnsResolver {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ExpiryMode;
+    use crate::mock_rpc::{ISSUER, MockMetadata, MockRpc, account, hosted_xns_ledger, xns_ledger};
+    use crate::models::ExpiryStatus;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_resolver_creation() {
@@ -358,10 +1411,293 @@ mod tests {
         assert!(resolver.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_with_url_validates() {
+        let resolver = XnsResolver::with_url(XrplNetwork::Mainnet, "localhost:5005".to_string()).await;
+        assert!(matches!(resolver, Err(XnsError::ConfigError(_))));
+    }
+
     #[tokio::test]
     async fn test_invalid_domain() {
         let resolver = XnsResolver::new(XrplNetwork::Mainnet).await.unwrap();
         let result = resolver.resolve("invalid.com").await;
         assert!(matches!(result, Err(XnsError::InvalidDomain(_))));
     }
+
+    #[tokio::test]
+    async fn test_tld_routing() {
+        let resolver = XnsResolver::builder()
+            .tld(NamingService::XNS, ".XRPL")
+            .build()
+            .await
+            .unwrap();
+
+        assert!(resolver.parse_name("ckelley.xrpl").is_ok());
+        assert!(resolver.parse_name("ckelley.xrp").is_ok());
+        assert!(matches!(resolver.parse_name("ckelley.com"), Err(XnsError::InvalidDomain(_))));
+
+        let xns = resolver.parse_name("ckelley.xrpl").unwrap();
+        let services: Vec<NamingService> = resolver.providers_for(&xns).map(|p| p.service()).collect();
+        assert_eq!(services, vec![NamingService::XNS]);
+    }
+
+    #[tokio::test]
+    async fn test_resolution_policy() {
+        let registration = |service: NamingService, owner: &str| {
+            DomainInfo::new("alice.xrp", owner, OwnerStatus::Ledger, format!("{}-nft", service), service)
+        };
+        let both = || {
+            vec![
+                registration(NamingService::XNS, "rAlice"),
+                registration(NamingService::XRPDomains, "rMallory"),
+            ]
+        };
+        let name = DomainName::parse("alice.xrp").unwrap();
+        let resolver = |policy| async move {
+            XnsResolver::builder().resolution_policy(policy).build().await.unwrap()
+        };
+
+        let preferred = resolver(ResolutionPolicy::Preferred).await.settle(&name, both()).unwrap();
+        assert_eq!(preferred.owner, "rAlice");
+        assert!(preferred.other_registrations.is_empty());
+
+        let unique = resolver(ResolutionPolicy::RequireUnique).await;
+        assert!(matches!(unique.settle(&name, both()), Err(XnsError::AmbiguousDomain(_))));
+        assert!(unique.settle(&name, both().split_off(1)).is_ok());
+
+        let all = resolver(ResolutionPolicy::ReturnAll).await.settle(&name, both()).unwrap();
+        assert_eq!(all.service, NamingService::XNS);
+        assert_eq!(all.other_registrations.len(), 1);
+        assert_eq!(all.other_registrations[0].owner, "rMallory");
+        assert_eq!(all.other_registrations[0].service, NamingService::XRPDomains);
+    }
+
+    #[tokio::test]
+    async fn test_expiry_mode_on_cached_name() {
+        let expired = DomainInfo {
+            expires_at: Some(unix_now() - 3600),
+            ..DomainInfo::test("alice.xrp", "rAlice")
+        };
+        let resolver = |mode, grace_period| {
+            let expired = expired.clone();
+            async move {
+                let resolver = XnsResolver::builder()
+                    .expiry_mode(mode)
+                    .expiry_grace_period(grace_period)
+                    .build()
+                    .await
+                    .unwrap();
+                resolver.cache_domain("alice.xrp", &expired).await;
+                resolver
+            }
+        };
+
+        let flagged = resolver(ExpiryMode::Flag, Duration::from_secs(60)).await;
+        assert_eq!(flagged.resolve("alice.xrp").await.unwrap().expiry, ExpiryStatus::Expired);
+
+        let grace = resolver(ExpiryMode::Enforce, Duration::from_secs(86_400)).await;
+        assert_eq!(grace.resolve("alice.xrp").await.unwrap().expiry, ExpiryStatus::GracePeriod);
+
+        let enforced = resolver(ExpiryMode::Enforce, Duration::ZERO).await;
+        assert!(matches!(enforced.resolve("alice.xrp").await, Err(XnsError::DomainExpired(_))));
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_hit() {
+        let resolver = XnsResolver::new(XrplNetwork::Mainnet).await.unwrap();
+        resolver.cache_not_found("nobody.xrp").await;
+
+        let result = resolver.resolve("Nobody.xrp").await;
+        assert!(matches!(result, Err(XnsError::DomainNotFound(_))));
+
+        let stats = resolver.cache_stats();
+        assert_eq!(stats.negative_hits, 1);
+        assert_eq!(stats.misses, 0);
+    }
+
+    #[tokio::test]
+    async fn test_subdomain_of_missing_name() {
+        let resolver = XnsResolver::new(XrplNetwork::Mainnet).await.unwrap();
+        resolver.cache_not_found("nobody.xrp").await;
+
+        let result = resolver.resolve("Pay.nobody.xrp").await;
+        assert!(matches!(result, Err(XnsError::DomainNotFound(_))));
+        assert_eq!(resolver.cache_stats().negative_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stale_while_revalidate() {
+        let resolver = XnsResolver::builder()
+            .stale_while_revalidate(Duration::from_secs(600))
+            .build()
+            .await
+            .unwrap();
+
+        let info = DomainInfo {
+            resolved_at: Some(unix_now() - 400),
+            ..DomainInfo::test("ckelley.xrp", "rAlice")
+        };
+        resolver.cache_domain("ckelley.xrp", &info).await;
+        // Pretend a refresh is already running so the test stays offline
+        resolver.refreshing.lock().unwrap().insert("ckelley.xrp".to_string());
+
+        let stale = resolver.resolve("ckelley.xrp").await.unwrap();
+        assert!(stale.is_stale());
+        assert!(stale.stale_age.unwrap() >= Duration::from_secs(400));
+        assert_eq!(resolver.cache_stats().stale_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_concurrency_config() {
+        let resolver = XnsResolver::builder()
+            .metadata_concurrency(4)
+            .metadata_pacing(None)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(resolver.metadata_concurrency, 4);
+        assert_eq!(resolver.metadata_semaphore.available_permits(), 4);
+        assert!(resolver.metadata_pacing.is_none());
+
+        let invalid = XnsResolver::builder().metadata_concurrency(0).build().await;
+        assert!(matches!(invalid, Err(XnsError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_metadata_fetches_bounded_and_stopped_at_match() {
+        let owner = account(0x11);
+        let names: Vec<String> = (0..8).map(|idx| format!("name{}.xrp", idx)).collect();
+        let ledger: Vec<(&str, &str)> = names.iter().map(|name| (name.as_str(), owner.as_str())).collect();
+        let metadata = MockMetadata::start(Duration::from_millis(50)).await;
+        let rpc = MockRpc::start(hosted_xns_ledger(&ledger, &metadata)).await;
+        let resolver = rpc.resolver().metadata_concurrency(2).build().await.unwrap();
+
+        resolver.resolve("name0.xrp").await.unwrap();
+        let requested = metadata.requests();
+        assert!(requested < names.len());
+
+        // Nothing else is fetched once the name is found
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(metadata.requests(), requested);
+
+        let batch = resolver.resolve_many(&names.iter().map(String::as_str).collect::<Vec<_>>()).await;
+        assert_eq!(batch.stats.resolved, names.len());
+        assert_eq!(metadata.peak(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_metadata_fetch_is_dropped() {
+        let alice = account(0x11);
+        let metadata = MockMetadata::start(Duration::from_millis(200)).await;
+        let rpc = MockRpc::start(hosted_xns_ledger(&[("alice.xrp", &alice)], &metadata)).await;
+        let resolver = rpc.resolver().metadata_concurrency(2).build().await.unwrap();
+
+        // Both callers give up while the metadata request is in flight
+        let first = resolver.resolve("alice.xrp");
+        let second = resolver.resolve("alice.xrp");
+        let waited = tokio::time::timeout(Duration::from_millis(100), futures::future::join(first, second)).await;
+        assert!(waited.is_err());
+        assert_eq!(metadata.requests(), 1);
+        assert_eq!(resolver.metadata_semaphore.available_permits(), 2);
+
+        // The next call starts its own fetch instead of joining the abandoned one
+        let info = resolver.resolve("alice.xrp").await.unwrap();
+        assert_eq!(info.owner, alice);
+        assert_eq!(metadata.requests(), 2);
+        assert_eq!(resolver.metadata_semaphore.available_permits(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_primary_name() {
+        let (alice, bob, carol) = (account(0x11), account(0x22), account(0x33));
+        let ledger = xns_ledger(&[("alice.xrp", &alice)], &[(&alice, "alice.xrp"), (&bob, "alice.xrp")]);
+        let did_reads = Arc::new(AtomicUsize::new(0));
+        let counted = did_reads.clone();
+        let rpc = MockRpc::start(move |method, params| {
+            if method == "ledger_entry" {
+                counted.fetch_add(1, Ordering::SeqCst);
+                return json!({ "error": "internal" });
+            }
+            ledger(method, params)
+        })
+        .await;
+
+        let resolver = rpc.resolver().build().await.unwrap();
+        assert_eq!(resolver.primary_name(&alice).await.unwrap().as_deref(), Some("alice.xrp"));
+        // Claimed by another account than the owner
+        assert_eq!(resolver.primary_name(&bob).await.unwrap(), None);
+        assert_eq!(resolver.primary_name(&carol).await.unwrap(), None);
+        assert_eq!(did_reads.load(Ordering::SeqCst), 0);
+
+        // An unreadable DID falls back to the memo
+        let resolver = rpc.resolver().did_records(true).build().await.unwrap();
+        assert_eq!(resolver.primary_name(&alice).await.unwrap().as_deref(), Some("alice.xrp"));
+        assert!(did_reads.load(Ordering::SeqCst) > 0);
+    }
+
+    #[tokio::test]
+    async fn test_primary_name_ignores_cached_owner() {
+        let (alice, bob) = (account(0x11), account(0x22));
+        let claims = [(alice.as_str(), "alice.xrp"), (bob.as_str(), "alice.xrp")];
+        let rpc = MockRpc::start(xns_ledger(&[("alice.xrp", &bob)], &claims)).await;
+        let resolver = rpc.resolver().build().await.unwrap();
+
+        // alice.xrp was sold to bob since alice's lookup was cached
+        let nft_id = format!("00080000{}{:016X}", hex::encode_upper(address::decode_account_id(ISSUER).unwrap()), 0);
+        let info = DomainInfo::test("alice.xrp", &alice);
+        resolver.cache_domain("alice.xrp", &info).await;
+        let owner = CachedValue::Owner { owner: alice.clone(), status: OwnerStatus::Clio };
+        resolver.cache.insert(&owner_key(&nft_id), owner, Duration::from_secs(60)).await;
+        assert_eq!(resolver.resolve("alice.xrp").await.unwrap().owner, alice);
+        assert_eq!(resolver.resolve_uncached(&resolver.parse_name("alice.xrp").unwrap()).await.unwrap().owner, alice);
+        assert_eq!(resolver.primary_name(&alice).await.unwrap(), None);
+
+        // while bob's claim is confirmed
+        assert_eq!(resolver.primary_name(&bob).await.unwrap().as_deref(), Some("alice.xrp"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_many() {
+        let (alice, bob) = (account(0x11), account(0x22));
+        let ledger = xns_ledger(&[("alice.xrp", &alice), ("bob.xrp", &bob)], &[]);
+        let listings = Arc::new(AtomicUsize::new(0));
+        let counted = listings.clone();
+        let rpc = MockRpc::start(move |method, params| {
+            if method == "nfts_by_issuer" {
+                counted.fetch_add(1, Ordering::SeqCst);
+            }
+            ledger(method, params)
+        })
+        .await;
+        let resolver = rpc.resolver().build().await.unwrap();
+
+        let batch = resolver
+            .resolve_many(&["bob.xrp", "alice.xrp", "missing.xrp", "bob.xrp", "alice.xrp"])
+            .await;
+
+        let owners: Vec<Option<&str>> = batch.results.iter().map(|r| r.as_ref().ok().map(|i| i.owner.as_str())).collect();
+        assert_eq!(owners, [Some(bob.as_str()), Some(&alice), None, Some(&bob), Some(&alice)]);
+        assert!(matches!(batch.results[2], Err(XnsError::DomainNotFound(_))));
+        assert_eq!(batch.stats.total, 5);
+        assert_eq!(batch.stats.unique, 3);
+        assert_eq!(batch.stats.resolved, 4);
+        assert_eq!(batch.stats.not_found, 1);
+        assert_eq!(batch.stats.issuers_listed, 1);
+        assert_eq!(batch.stats.nfts_scanned, 2);
+        assert_eq!(batch.stats.metadata_fetched, 2);
+        assert_eq!(listings.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_many_invalid_domains() {
+        let resolver = XnsResolver::new(XrplNetwork::Mainnet).await.unwrap();
+        let batch = resolver.resolve_many(&["invalid.com", "also.invalid"]).await;
+
+        assert_eq!(batch.results.len(), 2);
+        assert!(batch.results.iter().all(|r| matches!(r, Err(XnsError::InvalidDomain(_)))));
+        assert_eq!(batch.stats.failed, 2);
+        assert_eq!(batch.stats.unique, 0);
+        assert_eq!(batch.stats.issuers_listed, 0);
+    }
 }
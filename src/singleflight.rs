@@ -0,0 +1,156 @@
+//! Single-flight deduplication of concurrent lookups
+//!
+//! When several callers ask for the same key at once, only the first starts
+//! the underlying operation; the others wait on it and receive a clone of its
+//! result or error. Once the operation completes the key is forgotten, so the
+//! next call starts a fresh flight.
+//!
+//! The map only holds weak handles: when every caller waiting on a flight is
+//! cancelled, the operation is dropped with them instead of lingering
+//! unpolled, and the next call for the key starts over.
+
+use crate::error::XnsResult;
+use futures::FutureExt;
+use futures::future::{BoxFuture, Shared, WeakShared};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+type Operation<T> = BoxFuture<'static, XnsResult<T>>;
+type Flight<T> = Shared<Operation<T>>;
+
+/// Coalesces concurrent operations that share a key
+pub(crate) struct SingleFlight<T> {
+    calls: Arc<Mutex<HashMap<String, WeakShared<Operation<T>>>>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> SingleFlight<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            calls: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Run `operation` for `key`, or join the flight already running for it
+    ///
+    /// The operation keeps running as long as any caller is still waiting on
+    /// it, even if the caller that started it is cancelled. Once no caller is
+    /// left it is dropped.
+    pub(crate) async fn run<F, Fut>(&self, key: &str, operation: F) -> XnsResult<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = XnsResult<T>> + Send + 'static,
+    {
+        let flight = {
+            let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+
+            match calls.get(key).and_then(WeakShared::upgrade) {
+                Some(flight) => {
+                    tracing::trace!("Joining in-flight lookup for {}", key);
+                    flight
+                }
+                None => {
+                    let calls_handle = Arc::clone(&self.calls);
+                    let owned_key = key.to_string();
+                    let operation = operation();
+
+                    let flight: Flight<T> = async move {
+                        let result = operation.await;
+                        calls_handle
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .remove(&owned_key);
+                        result
+                    }
+                    .boxed()
+                    .shared();
+
+                    // Forget flights abandoned by all their callers
+                    calls.retain(|_, flight| flight.upgrade().is_some());
+                    if let Some(weak) = flight.downgrade() {
+                        calls.insert(key.to_string(), weak);
+                    }
+                    flight
+                }
+            }
+        };
+
+        flight.await
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::XnsError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_operation() {
+        let flights = Arc::new(SingleFlight::<String>::new());
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let callers = (0..20).map(|_| {
+            let flights = Arc::clone(&flights);
+            let runs = Arc::clone(&runs);
+            tokio::spawn(async move {
+                flights
+                    .run("ckelley.xrp", || async move {
+                        runs.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Err::<String, _>(XnsError::NetworkError("timeout".to_string()))
+                    })
+                    .await
+            })
+        });
+
+        for result in futures::future::join_all(callers).await {
+            assert!(matches!(result.unwrap(), Err(XnsError::NetworkError(_))));
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // The key is released once the flight lands
+        let result = flights.run("ckelley.xrp", || async { Ok("rAlice".to_string()) }).await;
+        assert_eq!(result.unwrap(), "rAlice");
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_flight_is_dropped() {
+        struct Dropped(Arc<AtomicUsize>);
+        impl Drop for Dropped {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let flights = SingleFlight::<String>::new();
+        let dropped = Arc::new(AtomicUsize::new(0));
+
+        let stalled = |dropped: Arc<AtomicUsize>| {
+            let guard = Dropped(dropped);
+            async move {
+                let _guard = guard;
+                futures::future::pending::<XnsResult<String>>().await
+            }
+        };
+        let first = flights.run("ckelley.xrp", || stalled(Arc::clone(&dropped)));
+        let second = flights.run("ckelley.xrp", || stalled(Arc::clone(&dropped)));
+        let waited = tokio::time::timeout(Duration::from_millis(20), futures::future::join(first, second)).await;
+        assert!(waited.is_err());
+
+        // Only one operation started, and it went away with its callers
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        assert!(flights.calls.lock().unwrap().get("ckelley.xrp").and_then(WeakShared::upgrade).is_none());
+
+        let result = flights.run("ckelley.xrp", || async { Ok("rAlice".to_string()) }).await;
+        assert_eq!(result.unwrap(), "rAlice");
+        assert!(flights.calls.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,596 @@
+//! Watch API for domain ownership and record changes
+//!
+//! [`XnsResolver::watch`] returns a stream of [`DomainEvent`]s for a single
+//! domain. The first event is the domain's current state, after which only
+//! changes are emitted. Changes are detected either by polling the ledger at a
+//! fixed interval or by following, over a WebSocket `accounts` subscription,
+//! the accounts whose transactions can change the domain.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use xns_sdk_rs::{DomainEvent, XnsResolver, XrplNetwork};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let resolver = XnsResolver::new(XrplNetwork::Mainnet).await?;
+//!
+//!     let mut events = resolver.watch("ckelley.xrp");
+//!     while let Some(event) = events.next().await {
+//!         match event? {
+//!             DomainEvent::OwnerChanged { previous_owner, info } => {
+//!                 println!("{} moved from {} to {}", info.domain, previous_owner, info.owner);
+//!             }
+//!             other => println!("{:?}", other),
+//!         }
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::domain::DomainName;
+use crate::error::{XnsError, XnsResult};
+use crate::models::{DomainInfo, unix_now};
+use crate::resolver::XnsResolver;
+use futures::stream::{self, BoxStream};
+use futures::{Sink, SinkExt, StreamExt};
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+/// Stream of events returned by [`XnsResolver::watch`]
+pub type DomainWatch = BoxStream<'static, XnsResult<DomainEvent>>;
+
+/// Change to a watched domain
+#[derive(Debug, Clone)]
+pub enum DomainEvent {
+    /// State of the domain when it was first seen by the watch
+    Initial(DomainInfo),
+
+    /// The domain NFT moved to a different account
+    OwnerChanged {
+        previous_owner: String,
+        info: DomainInfo,
+    },
+
+    /// Addresses, text records or the backing NFT changed without a transfer
+    RecordsUpdated(DomainInfo),
+
+    /// The domain NFT was burned or is no longer issued by its naming service
+    Burned { domain: String, nft_id: String },
+
+    /// The domain passed its expiration timestamp
+    Expired { domain: String, expires_at: u64 },
+}
+
+/// How a watch detects changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchMode {
+    /// Re-resolve the domain at a fixed interval
+    Poll { interval: Duration },
+
+    /// Subscribe over WebSocket to the accounts whose transactions can change
+    /// the domain, and re-resolve it when one of them has a validated
+    /// transaction or when it expires
+    ///
+    /// Those are the owner of the name NFT and, for a subdomain, the owner of
+    /// its registered name. Until the domain resolves, the issuers of the
+    /// naming services that could mint it are followed instead.
+    LedgerSubscription,
+}
+
+/// Options for [`XnsResolver::watch_with`]
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Change detection strategy
+    pub mode: WatchMode,
+
+    /// Delay before reconnecting a dropped ledger subscription
+    pub reconnect_delay: Duration,
+
+    /// Number of undelivered events buffered before the watch waits
+    pub buffer: usize,
+}
+
+impl WatchOptions {
+    /// Poll the ledger every `interval`
+    pub fn poll(interval: Duration) -> Self {
+        Self {
+            mode: WatchMode::Poll { interval },
+            ..Self::default()
+        }
+    }
+
+    /// Re-check the domain when an account that can change it transacts
+    pub fn subscribe() -> Self {
+        Self {
+            mode: WatchMode::LedgerSubscription,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            mode: WatchMode::Poll {
+                interval: Duration::from_secs(60),
+            },
+            reconnect_delay: Duration::from_secs(5),
+            buffer: 16,
+        }
+    }
+}
+
+/// Last observed state of a watched domain, used to deduplicate events
+#[derive(Debug, Default)]
+struct WatchState {
+    last: Option<DomainInfo>,
+    missing_reported: bool,
+    expired_reported: Option<u64>,
+}
+
+impl WatchState {
+    /// Time until the last seen expiration, if it has not passed yet
+    fn until_expiry(&self, now: u64) -> Option<Duration> {
+        let expires_at = self.last.as_ref()?.expires_at?;
+        (expires_at > now).then(|| Duration::from_secs(expires_at - now))
+    }
+
+    /// Compare a fresh resolution against the last state and produce events
+    fn apply(&mut self, outcome: XnsResult<DomainInfo>, now: u64) -> Vec<XnsResult<DomainEvent>> {
+        let mut events = Vec::new();
+
+        match outcome {
+            Ok(info) => {
+                self.missing_reported = false;
+
+                match self.last.take() {
+                    None => events.push(Ok(DomainEvent::Initial(info.clone()))),
+                    Some(previous) => {
+                        // An owner that could not be looked up is not a transfer
+                        let owners = (previous.known_owner(), info.known_owner());
+                        if let (Some(previous_owner), Some(owner)) = owners
+                            && previous_owner != owner
+                        {
+                            events.push(Ok(DomainEvent::OwnerChanged {
+                                previous_owner: previous_owner.to_string(),
+                                info: info.clone(),
+                            }));
+                        } else if previous.nft_id != info.nft_id
+                            || previous.addresses != info.addresses
+                            || previous.text_records != info.text_records
+                            || previous.expires_at != info.expires_at
+                        {
+                            events.push(Ok(DomainEvent::RecordsUpdated(info.clone())));
+                        }
+                    }
+                }
+
+                match info.expires_at {
+                    Some(expires_at) if expires_at <= now => {
+                        if self.expired_reported != Some(expires_at) {
+                            self.expired_reported = Some(expires_at);
+                            events.push(Ok(DomainEvent::Expired {
+                                domain: info.domain.clone(),
+                                expires_at,
+                            }));
+                        }
+                    }
+                    _ => self.expired_reported = None,
+                }
+
+                self.last = Some(info);
+            }
+            Err(XnsError::DomainNotFound(domain)) => {
+                if let Some(previous) = self.last.take() {
+                    events.push(Ok(DomainEvent::Burned {
+                        domain: previous.domain,
+                        nft_id: previous.nft_id,
+                    }));
+                    self.missing_reported = true;
+                } else if !self.missing_reported {
+                    events.push(Err(XnsError::DomainNotFound(domain)));
+                    self.missing_reported = true;
+                }
+                self.expired_reported = None;
+            }
+            Err(XnsError::DomainExpired(domain)) => {
+                // Refused past its grace period; the expiry itself was
+                // usually reported while the name was in its grace period
+                match self.last.take().and_then(|previous| previous.expires_at) {
+                    Some(expires_at) if self.expired_reported != Some(expires_at) => {
+                        self.expired_reported = Some(expires_at);
+                        events.push(Ok(DomainEvent::Expired { domain, expires_at }));
+                    }
+                    Some(_) => {}
+                    None if !self.missing_reported => events.push(Err(XnsError::DomainExpired(domain))),
+                    None => {}
+                }
+                self.missing_reported = true;
+            }
+            Err(e) => events.push(Err(e)),
+        }
+
+        events
+    }
+}
+
+/// Spawn the background task driving a watch and return its event stream
+pub(crate) fn spawn(resolver: XnsResolver, domain: String, options: WatchOptions) -> DomainWatch {
+    let (tx, rx) = mpsc::channel(options.buffer.max(1));
+
+    tokio::spawn(async move {
+        let mut state = WatchState::default();
+
+        let domain = match resolver.parse_name(&domain) {
+            Ok(domain) => domain,
+            Err(e) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        };
+
+        match options.mode {
+            WatchMode::Poll { interval } => loop {
+                if !check(&resolver, &domain, &mut state, &tx).await {
+                    return;
+                }
+
+                tokio::select! {
+                    _ = sleep(interval) => {}
+                    _ = tx.closed() => return,
+                }
+            },
+            WatchMode::LedgerSubscription => {
+                if !check(&resolver, &domain, &mut state, &tx).await {
+                    return;
+                }
+
+                loop {
+                    if let Err(e) = follow_accounts(&resolver, &domain, &mut state, &tx).await {
+                        tracing::warn!("Ledger subscription for {} dropped: {}", domain, e);
+                        if tx.send(Err(e)).await.is_err() {
+                            return;
+                        }
+                    }
+
+                    if tx.is_closed() {
+                        return;
+                    }
+
+                    tokio::select! {
+                        _ = sleep(options.reconnect_delay) => {}
+                        _ = tx.closed() => return,
+                    }
+                }
+            }
+        }
+    });
+
+    stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|event| (event, rx)) }).boxed()
+}
+
+/// Re-resolve the domain and forward any resulting events.
+///
+/// Cached state behind the last result is dropped first, so a change made
+/// within the cache TTLs is not hidden. Returns `false` once the receiving
+/// side of the watch has been dropped.
+async fn check(
+    resolver: &XnsResolver,
+    domain: &DomainName,
+    state: &mut WatchState,
+    tx: &mpsc::Sender<XnsResult<DomainEvent>>,
+) -> bool {
+    if let Some(last) = &state.last {
+        resolver.forget(last).await;
+    }
+    let outcome = resolver.resolve_uncached(domain).await;
+
+    for event in state.apply(outcome, unix_now()) {
+        if tx.send(event).await.is_err() {
+            return false;
+        }
+    }
+
+    !tx.is_closed()
+}
+
+/// Follow the accounts that can change the domain over WebSocket until the
+/// connection ends
+async fn follow_accounts(
+    resolver: &XnsResolver,
+    domain: &DomainName,
+    state: &mut WatchState,
+    tx: &mpsc::Sender<XnsResult<DomainEvent>>,
+) -> XnsResult<()> {
+    let network = resolver.network();
+    let ws_url = network
+        .ws_url()
+        .ok_or_else(|| XnsError::ConfigError(format!("Network {} has no WebSocket URL", network)))?;
+    tracing::debug!("Following accounts at {} for {}", ws_url, domain);
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(ws_url)
+        .await
+        .map_err(|e| XnsError::NetworkError(format!("WebSocket connect failed: {}", e)))?;
+
+    let mut accounts = watched_accounts(resolver, domain, state).await;
+    send(&mut socket, json!({ "command": "subscribe", "accounts": accounts })).await?;
+
+    // Catch changes made before the subscription started
+    let mut recheck = true;
+    let mut last_ledger = None;
+
+    loop {
+        if recheck {
+            if !check(resolver, domain, state, tx).await {
+                return Ok(());
+            }
+
+            let current = watched_accounts(resolver, domain, state).await;
+            if current != accounts {
+                tracing::debug!("Following {:?} instead of {:?} for {}", current, accounts, domain);
+                send(&mut socket, json!({ "command": "unsubscribe", "accounts": accounts })).await?;
+                send(&mut socket, json!({ "command": "subscribe", "accounts": current })).await?;
+                accounts = current;
+            }
+        }
+
+        let until_expiry = state.until_expiry(unix_now());
+        recheck = tokio::select! {
+            message = socket.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    // One check covers every transaction in a ledger
+                    let value: Value = serde_json::from_str(&text)?;
+                    match validated_ledger(&value) {
+                        Some(ledger) if last_ledger != Some(ledger) => {
+                            last_ledger = Some(ledger);
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(XnsError::NetworkError("WebSocket closed".to_string()));
+                }
+                Some(Ok(_)) => false,
+                Some(Err(e)) => {
+                    return Err(XnsError::NetworkError(format!("WebSocket error: {}", e)));
+                }
+            },
+            _ = sleep(until_expiry.unwrap_or_default()), if until_expiry.is_some() => true,
+            _ = tx.closed() => return Ok(()),
+        };
+    }
+}
+
+async fn send<S>(socket: &mut S, command: Value) -> XnsResult<()>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    socket
+        .send(Message::Text(command.to_string()))
+        .await
+        .map_err(|e| XnsError::NetworkError(format!("WebSocket send failed: {}", e)))
+}
+
+/// Accounts whose transactions can change the domain, from its last state
+async fn watched_accounts(resolver: &XnsResolver, domain: &DomainName, state: &WatchState) -> Vec<String> {
+    let owner = state.last.as_ref().and_then(|info| info.known_owner());
+    let registered = domain.registered();
+    let parent = if domain.is_subdomain() {
+        resolver.resolve_name(&registered).await.ok()
+    } else {
+        None
+    };
+    let parent_owner = parent.as_ref().and_then(|info| info.known_owner());
+
+    affected_accounts(owner, parent_owner, &resolver.issuer_accounts(&registered))
+}
+
+/// Accounts that can change a domain with a given owner and, for a
+/// subdomain, owner of its registered name
+///
+/// Transfers and burns modify the owner's NFToken pages and record updates
+/// are the owner's memos and DID, so the owners are enough; with no owner
+/// known the name can only appear through a mint by one of the issuers.
+fn affected_accounts(owner: Option<&str>, parent_owner: Option<&str>, issuers: &[String]) -> Vec<String> {
+    let mut accounts: Vec<String> = owner.into_iter().chain(parent_owner).map(str::to_string).collect();
+    if accounts.is_empty() {
+        accounts = issuers.to_vec();
+    }
+    accounts.sort();
+    accounts.dedup();
+    accounts
+}
+
+/// Ledger of a validated transaction reported by an `accounts` subscription
+fn validated_ledger(message: &Value) -> Option<u64> {
+    if message.get("type")?.as_str()? != "transaction" || !message.get("validated")?.as_bool()? {
+        return None;
+    }
+    message.get("ledger_index")?.as_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{MockRpc, account, xns_ledger};
+    use crate::models::XrplNetwork;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::time::timeout;
+    use tokio_tungstenite::WebSocketStream;
+
+    fn info(owner: &str) -> DomainInfo {
+        DomainInfo::test("ckelley.xrp", owner)
+    }
+
+    #[test]
+    fn test_watch_state_deduplicates() {
+        let mut state = WatchState::default();
+
+        let events = state.apply(Ok(info("rAlice")), 0);
+        assert!(matches!(events.as_slice(), [Ok(DomainEvent::Initial(_))]));
+
+        assert!(state.apply(Ok(info("rAlice")), 0).is_empty());
+
+        let events = state.apply(Ok(info("rBob")), 0);
+        assert!(matches!(
+            events.as_slice(),
+            [Ok(DomainEvent::OwnerChanged { previous_owner, .. })] if previous_owner == "rAlice"
+        ));
+
+        let events = state.apply(Err(XnsError::DomainNotFound("ckelley.xrp".to_string())), 0);
+        assert!(matches!(events.as_slice(), [Ok(DomainEvent::Burned { .. })]));
+        assert!(state
+            .apply(Err(XnsError::DomainNotFound("ckelley.xrp".to_string())), 0)
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_poll_sees_owner_change_within_owner_ttl() {
+        let (alice, bob) = (account(0x11), account(0x22));
+        let ledger = xns_ledger(&[("alice.xrp", &alice)], &[]);
+        let owner = Arc::new(Mutex::new(alice.clone()));
+        let current = owner.clone();
+        let rpc = MockRpc::start(move |method, params| {
+            let mut result = ledger(method, params);
+            if method == "nft_info" {
+                result["owner"] = json!(*current.lock().unwrap());
+            }
+            result
+        })
+        .await;
+        let resolver = rpc.resolver().build().await.unwrap();
+
+        let mut events = resolver.watch_with("alice.xrp", WatchOptions::poll(Duration::from_millis(50)));
+        let wait = Duration::from_secs(10);
+        let event = timeout(wait, events.next()).await.unwrap().unwrap().unwrap();
+        assert!(matches!(event, DomainEvent::Initial(info) if info.owner == alice));
+
+        // Well within the owner cache TTL
+        *owner.lock().unwrap() = bob.clone();
+        let event = timeout(wait, events.next()).await.unwrap().unwrap().unwrap();
+        assert!(matches!(
+            event,
+            DomainEvent::OwnerChanged { previous_owner, info } if previous_owner == alice && info.owner == bob
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_follows_owner() {
+        let (alice, bob) = (account(0x11), account(0x22));
+        let ledger = xns_ledger(&[("alice.xrp", &alice)], &[]);
+        let owner = Arc::new(Mutex::new(alice.clone()));
+        let owner_lookups = Arc::new(AtomicUsize::new(0));
+        let (current, lookups) = (owner.clone(), owner_lookups.clone());
+        let rpc = MockRpc::start(move |method, params| {
+            let mut result = ledger(method, params);
+            if method == "nft_info" {
+                lookups.fetch_add(1, Ordering::SeqCst);
+                result["owner"] = json!(*current.lock().unwrap());
+            }
+            result
+        })
+        .await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+        let network = XrplNetwork::Custom(rpc.custom_network().with_ws_url(ws_url));
+        let resolver = rpc.resolver().network(network).build().await.unwrap();
+
+        let mut events = resolver.watch_with("alice.xrp", WatchOptions::subscribe());
+        let wait = Duration::from_secs(10);
+        let event = timeout(wait, events.next()).await.unwrap().unwrap().unwrap();
+        assert!(matches!(event, DomainEvent::Initial(info) if info.owner == alice));
+
+        let (stream, _) = timeout(wait, listener.accept()).await.unwrap().unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        assert_eq!(command(&mut socket).await, json!({ "command": "subscribe", "accounts": [alice] }));
+
+        // Let the check made on subscribing finish before the transfer
+        timeout(wait, async {
+            while owner_lookups.load(Ordering::SeqCst) < 2 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        *owner.lock().unwrap() = bob.clone();
+        let transfer = json!({ "type": "transaction", "validated": true, "ledger_index": 10, "transaction": {} });
+        socket.send(Message::Text(transfer.to_string())).await.unwrap();
+
+        let event = timeout(wait, events.next()).await.unwrap().unwrap().unwrap();
+        assert!(matches!(
+            event,
+            DomainEvent::OwnerChanged { previous_owner, info } if previous_owner == alice && info.owner == bob
+        ));
+        assert_eq!(command(&mut socket).await, json!({ "command": "unsubscribe", "accounts": [alice] }));
+        assert_eq!(command(&mut socket).await, json!({ "command": "subscribe", "accounts": [bob] }));
+    }
+
+    /// Next command a watch sent to the mock WebSocket server
+    async fn command(socket: &mut WebSocketStream<TcpStream>) -> Value {
+        match timeout(Duration::from_secs(10), socket.next()).await.unwrap().unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_affected_accounts() {
+        let issuers = ["rIssuerB".to_string(), "rIssuerA".to_string()];
+
+        assert_eq!(affected_accounts(Some("rAlice"), None, &issuers), ["rAlice"]);
+        assert_eq!(affected_accounts(Some("rPay"), Some("rAlice"), &issuers), ["rAlice", "rPay"]);
+        assert_eq!(affected_accounts(Some("rAlice"), Some("rAlice"), &issuers), ["rAlice"]);
+        // Not resolved yet: wait for a mint
+        assert_eq!(affected_accounts(None, None, &issuers), ["rIssuerA", "rIssuerB"]);
+    }
+
+    #[test]
+    fn test_validated_ledger() {
+        let message = |kind: &str, validated: bool| {
+            json!({ "type": kind, "validated": validated, "ledger_index": 90_000_000, "transaction": {} })
+        };
+        assert_eq!(validated_ledger(&message("transaction", true)), Some(90_000_000));
+        assert_eq!(validated_ledger(&message("transaction", false)), None);
+        assert_eq!(validated_ledger(&message("ledgerClosed", true)), None);
+        assert_eq!(validated_ledger(&json!({ "result": {}, "status": "success" })), None);
+    }
+
+    #[test]
+    fn test_until_expiry() {
+        let mut state = WatchState::default();
+        assert_eq!(state.until_expiry(50), None);
+
+        let mut expiring = info("rAlice");
+        expiring.expires_at = Some(100);
+        state.apply(Ok(expiring), 50);
+        assert_eq!(state.until_expiry(50), Some(Duration::from_secs(50)));
+        assert_eq!(state.until_expiry(100), None);
+    }
+
+    #[test]
+    fn test_watch_state_expiry_reported_once() {
+        let mut state = WatchState::default();
+        let mut expiring = info("rAlice");
+        expiring.expires_at = Some(100);
+
+        assert_eq!(state.apply(Ok(expiring.clone()), 50).len(), 1);
+        let events = state.apply(Ok(expiring.clone()), 150);
+        assert!(matches!(events.as_slice(), [Ok(DomainEvent::Expired { expires_at: 100, .. })]));
+        assert!(state.apply(Ok(expiring), 200).is_empty());
+
+        // Refused once the grace period ends, without a second event
+        let refused = || Err(XnsError::DomainExpired("ckelley.xrp".to_string()));
+        assert!(state.apply(refused(), 300).is_empty());
+        assert!(state.apply(refused(), 400).is_empty());
+    }
+}